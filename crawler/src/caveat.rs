@@ -0,0 +1,156 @@
+//! 営業時間の注意書きの分類
+//!
+//! 「※」で始まる行や時刻の書かれていない行、営業時間の括弧書きのうち、
+//! 下の表のどれかに当てはまるものを残す。
+//!
+//! regex クレートには先読みがないので、「予約不要」のような打ち消しは
+//! 表の 3 列目で先に取り除いてから照合する。
+
+use kanda_model::{BussinessHours, Caveat, CaveatFlag};
+use regex::Regex;
+
+/// 分類、当てはまる表記、照合の前に取り除く表記
+const PATTERNS: &[(CaveatFlag, &str, Option<&str>)] = &[
+    (
+        CaveatFlag::SellsOutEarly,
+        "なくなり次第|売り切れ|品切れ",
+        None,
+    ),
+    (CaveatFlag::MayBePrivate, "貸し?切", None),
+    (
+        CaveatFlag::ReservationNeeded,
+        "予約",
+        Some("予約(?:は|の)?(?:不要|不可|なし|無し|できません|を?受け付けておりません)"),
+    ),
+    (
+        CaveatFlag::ExtendedIfBusy,
+        "お客様がいれば|以降も営業",
+        None,
+    ),
+    (
+        CaveatFlag::MayCloseEarly,
+        "早じまい|いらっしゃらない場合",
+        None,
+    ),
+];
+
+/// 営業時間の元の文字列と、そのパース結果から注意書きを集める
+pub fn collect_caveats(s: &str, bhs: &[BussinessHours]) -> Vec<Caveat> {
+    let time_re = Regex::new(r"\d[:：]\d").unwrap();
+    let patterns = PATTERNS
+        .iter()
+        .map(|(flag, re, unless)| {
+            (
+                *flag,
+                Regex::new(re).unwrap(),
+                unless.map(|re| Regex::new(re).unwrap()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let lines = s
+        .split("<br>")
+        .map(|l| l.trim())
+        .filter(|l| l.starts_with('※') || !l.is_empty() && !time_re.is_match(l));
+    let notes = bhs.iter().filter_map(|bh| bh.note.as_deref());
+
+    let mut ret: Vec<Caveat> = vec![];
+
+    for text in lines.chain(notes) {
+        if ret.iter().any(|c| c.text == text) {
+            continue;
+        }
+
+        let flags = patterns
+            .iter()
+            .filter(|(_, re, unless)| match unless {
+                Some(unless) => re.is_match(&unless.replace_all(text, "")),
+                None => re.is_match(text),
+            })
+            .map(|(flag, _, _)| *flag)
+            .collect::<Vec<_>>();
+
+        if !flags.is_empty() {
+            ret.push(Caveat {
+                text: text.to_string(),
+                flags,
+            });
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(s: &str) -> Vec<CaveatFlag> {
+        let caveats = collect_caveats(s, &[]);
+        caveats.into_iter().flat_map(|c| c.flags).collect()
+    }
+
+    #[test]
+    fn sells_out_early() {
+        assert_eq!(
+            flags("※ランチはなくなり次第終了"),
+            vec![CaveatFlag::SellsOutEarly]
+        );
+        assert_eq!(flags("※ランチはライス大盛り無料"), vec![]);
+    }
+
+    #[test]
+    fn may_be_private() {
+        assert_eq!(flags("※貸切の場合あり"), vec![CaveatFlag::MayBePrivate]);
+        assert_eq!(flags("※個室あり"), vec![]);
+    }
+
+    #[test]
+    fn reservation_needed() {
+        assert_eq!(
+            flags("※ディナーは事前予約（コース料理）のみ"),
+            vec![CaveatFlag::ReservationNeeded]
+        );
+        assert_eq!(
+            flags("※前日までのご予約となります。"),
+            vec![CaveatFlag::ReservationNeeded]
+        );
+        assert_eq!(flags("※予約不要"), vec![]);
+        assert_eq!(flags("※ご予約は不可"), vec![]);
+        assert_eq!(flags("※予約なしでどうぞ"), vec![]);
+        // 打ち消しと要予約が並んでいれば、要予約
+        assert_eq!(
+            flags("※ランチは予約不要、ディナーは要予約"),
+            vec![CaveatFlag::ReservationNeeded]
+        );
+    }
+
+    #[test]
+    fn extended_if_busy() {
+        assert_eq!(
+            flags("※お客様がいれば23時以降も営業"),
+            vec![CaveatFlag::ExtendedIfBusy]
+        );
+        assert_eq!(flags("※23時閉店"), vec![]);
+    }
+
+    #[test]
+    fn may_close_early() {
+        assert_eq!(
+            flags("※お客様がいらっしゃらない場合は早じまいすることがあります"),
+            vec![CaveatFlag::MayCloseEarly]
+        );
+        assert_eq!(flags("※ラストオーダーは閉店30分前"), vec![]);
+    }
+
+    #[test]
+    fn lines_and_notes() {
+        // 時刻の書かれた行は、「※」で始まらなければ注意書きにしない
+        assert_eq!(flags("11:00〜売り切れまで"), vec![]);
+        assert_eq!(flags("売り切れ次第終了"), vec![CaveatFlag::SellsOutEarly]);
+        assert_eq!(
+            flags("※11:00〜売り切れまで"),
+            vec![CaveatFlag::SellsOutEarly]
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! 店のページの表から電話番号や席数などを取り出す
+//!
+//! 表の行の並びや見出しは店によって違うので、見出しに含まれる語で行を探す。
+//! 見つからない項目や読めない項目は None のままにする。
+
+use kanda_model::{Link, Menu, Smoking};
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Default)]
+pub struct Details {
+    pub phone: Option<String>,
+    pub seats: Option<u32>,
+    pub smoking: Option<Smoking>,
+    pub links: Vec<Link>,
+}
+
+pub fn extract_details(table: &[(String, String)]) -> Details {
+    let mut ret = Details::default();
+
+    let link_key =
+        Regex::new(r"(?i)公式|サイト|ホームページ|HP|URL|SNS|ブログ|instagram|twitter|facebook")
+            .unwrap();
+
+    for (key, value) in table {
+        let text = plain_text(value);
+
+        if key.contains("電話") || key.to_uppercase().contains("TEL") {
+            ret.phone = ret.phone.or_else(|| phone(&text));
+        } else if key.contains("席") {
+            ret.seats = ret.seats.or_else(|| seats(&text));
+        } else if key.contains("煙") || key.contains("たばこ") || key.contains("タバコ") {
+            ret.smoking = ret.smoking.or_else(|| smoking(&text));
+        } else if link_key.is_match(key) {
+            for url in urls(value) {
+                if !ret.links.iter().any(|l| l.url == url) {
+                    ret.links.push(Link {
+                        label: key.clone(),
+                        url,
+                    });
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+/// 表の「メニュー」「価格」「メニュー紹介」などの行からグランプリのカレーを取り出す。
+/// `image` は表の外にある写真
+pub fn extract_menu(table: &[(String, String)], image: Option<&str>) -> Option<Menu> {
+    let mut name = None;
+    let mut price = None;
+    let mut description = None;
+    let mut row_image = None;
+
+    for (key, value) in table {
+        let text = plain_text(value).trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+
+        if key.contains("価格") || key.contains("値段") || key.contains("料金") {
+            price = price.or_else(|| yen(&text));
+        } else if ["紹介", "説明", "特徴", "こだわり", "コメント"]
+            .iter()
+            .any(|w| key.contains(w))
+            && !key.contains('店')
+        {
+            description = description.or(Some(text));
+        } else if key.contains("メニュー") || key.contains("カレー名") || key.contains("料理名")
+        {
+            // 「チキンカレー 1,200円」のように値段も書かれていることがある
+            price = price.or_else(|| yen(&text));
+            row_image = row_image.or_else(|| img_srcs(value).into_iter().next());
+            name = name.or(Some(text));
+        }
+    }
+
+    Some(Menu {
+        name: name?,
+        price,
+        description,
+        image: row_image.or_else(|| image.map(|s| s.to_string())),
+    })
+}
+
+fn yen(s: &str) -> Option<u32> {
+    let re = Regex::new(r"¥\s*([\d,]+)|([\d,]+)\s*円").unwrap();
+    let c = re.captures(s)?;
+    c.get(1)
+        .or_else(|| c.get(2))?
+        .as_str()
+        .replace(',', "")
+        .parse()
+        .ok()
+}
+
+/// 画像の URL。ページの順に並ぶ
+pub fn img_srcs(html: &str) -> Vec<String> {
+    let re = Regex::new(r#"<img\s[^>]*src="([^"]+)""#).unwrap();
+    re.captures_iter(html).map(|c| c[1].to_string()).collect()
+}
+
+/// タグを除いて NFKC で正規化する
+fn plain_text(html: &str) -> String {
+    let tag = Regex::new(r"<[^>]*>").unwrap();
+    tag.replace_all(html, " ").nfkc().collect()
+}
+
+fn phone(s: &str) -> Option<String> {
+    // NFKC ではハイフンに似た文字がそろわない
+    let s = s.replace(&['‐', '‑', '−', 'ー', '―', '–'][..], "-");
+    let re = Regex::new(r"0\d{1,4}-\d{1,4}-\d{3,4}|0\d{9,10}").unwrap();
+    re.find(&s).map(|m| m.as_str().to_string())
+}
+
+/// 「全30席（カウンター10席、テーブル20席）」なら 30、
+/// 「カウンター10席、テーブル20席」なら 30
+fn seats(s: &str) -> Option<u32> {
+    let total = Regex::new(r"(?:全|計)\s*(\d+)").unwrap();
+    if let Some(c) = total.captures(s) {
+        return c[1].parse().ok();
+    }
+
+    let each = Regex::new(r"(\d+)\s*席").unwrap();
+    let nums = each
+        .captures_iter(s)
+        .filter_map(|c| c[1].parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    if !nums.is_empty() {
+        return Some(nums.iter().sum());
+    }
+
+    let num = Regex::new(r"\d+").unwrap();
+    num.find(s).and_then(|m| m.as_str().parse().ok())
+}
+
+fn smoking(s: &str) -> Option<Smoking> {
+    if s.contains("分煙") || s.contains("喫煙席") {
+        Some(Smoking::Separated)
+    } else if s.contains("禁煙") || s.contains("不可") {
+        Some(Smoking::NoSmoking)
+    } else if s.contains("喫煙") || s.contains('可') {
+        Some(Smoking::Allowed)
+    } else {
+        None
+    }
+}
+
+/// リンク先と、本文にそのまま書かれた URL
+fn urls(html: &str) -> Vec<String> {
+    let href = Regex::new(r#"href="(https?://[^"]+)""#).unwrap();
+    let bare = Regex::new(r#"https?://[^\s<>"]+"#).unwrap();
+
+    let mut ret = href
+        .captures_iter(html)
+        .map(|c| c[1].replace("&amp;", "&"))
+        .collect::<Vec<_>>();
+    for m in bare.find_iter(&plain_text(html)) {
+        let url = m.as_str().replace("&amp;", "&");
+        if !ret.contains(&url) {
+            ret.push(url);
+        }
+    }
+    ret
+}
@@ -0,0 +1,180 @@
+//! ページの取得
+//!
+//! `Http` で実際に取得するほか、`Recorder` で保存したページを `Replay` で
+//! 読み返せるので、ネットワークなしでもスクレイピングを試せる。
+
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+pub trait Fetcher {
+    /// `url` のページの本文
+    fn fetch(&self, url: &str) -> Result<String>;
+}
+
+impl<F: Fetcher + ?Sized> Fetcher for Box<F> {
+    fn fetch(&self, url: &str) -> Result<String> {
+        (**self).fetch(url)
+    }
+}
+
+/// 失敗したときに試す回数
+const RETRIES: u32 = 5;
+
+/// 実際に HTTP で取得する
+pub struct Http {
+    /// サーバに負荷をかけないよう、リクエストごとに待つ時間
+    interval: Duration,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(1000),
+        }
+    }
+}
+
+impl Fetcher for Http {
+    /// 失敗したら間隔を倍にしながら `RETRIES` 回まで試す
+    fn fetch(&self, url: &str) -> Result<String> {
+        let mut wait = Duration::from_secs(1);
+        let mut i = 1;
+
+        loop {
+            let resp = ureq::get(url).timeout(Duration::from_secs(30)).call();
+            thread::sleep(self.interval);
+
+            let err = if let Some(err) = resp.synthetic_error() {
+                anyhow!("{}", err)
+            } else if !resp.ok() {
+                anyhow!("HTTP {}", resp.status())
+            } else {
+                match resp.into_string() {
+                    Ok(s) => return Ok(s),
+                    Err(e) => e.into(),
+                }
+            };
+
+            if i == RETRIES {
+                return Err(err.context(format!("failed to get {} ({} attempts)", url, RETRIES)));
+            }
+
+            eprintln!("failed to get: {}, {}; retrying in {:?}", url, err, wait);
+            thread::sleep(wait);
+            wait *= 2;
+            i += 1;
+        }
+    }
+}
+
+/// URL ごとにページを保存しておくディレクトリ
+///
+/// ファイル名は URL をパーセントエンコードしたものに `.html` をつけたもの。
+pub struct PageDir {
+    dir: PathBuf,
+}
+
+impl PageDir {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let mut name = String::new();
+        for b in url.bytes() {
+            if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.' {
+                name.push(b as char);
+            } else {
+                name.push_str(&format!("%{:02X}", b));
+            }
+        }
+        self.dir.join(name + ".html")
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        self.path(url).exists()
+    }
+
+    pub fn get(&self, url: &str) -> Option<String> {
+        fs::read_to_string(self.path(url)).ok()
+    }
+
+    pub fn put(&self, url: &str, body: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(url), body)?;
+        Ok(())
+    }
+}
+
+/// 保存しておいたページを返す。保存されていなければエラー
+pub struct Replay {
+    pages: PageDir,
+}
+
+impl Replay {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            pages: PageDir::new(dir),
+        }
+    }
+}
+
+impl Fetcher for Replay {
+    fn fetch(&self, url: &str) -> Result<String> {
+        self.pages
+            .get(url)
+            .ok_or_else(|| anyhow!("{} is not saved in {}", url, self.pages.dir.display()))
+    }
+}
+
+/// `inner` で取得したページを保存する
+pub struct Recorder<F> {
+    inner: F,
+    pages: PageDir,
+}
+
+impl<F> Recorder<F> {
+    pub fn new(inner: F, dir: impl AsRef<Path>) -> Self {
+        Self {
+            inner,
+            pages: PageDir::new(dir),
+        }
+    }
+}
+
+impl<F: Fetcher> Fetcher for Recorder<F> {
+    fn fetch(&self, url: &str) -> Result<String> {
+        let body = self.inner.fetch(url)?;
+        self.pages.put(url, &body)?;
+        Ok(body)
+    }
+}
+
+/// 保存済みのページはそれを返し、なければ `inner` で取得して保存する
+pub struct Cached<F> {
+    recorder: Recorder<F>,
+}
+
+impl<F> Cached<F> {
+    pub fn new(inner: F, dir: impl AsRef<Path>) -> Self {
+        Self {
+            recorder: Recorder::new(inner, dir),
+        }
+    }
+}
+
+impl<F: Fetcher> Fetcher for Cached<F> {
+    fn fetch(&self, url: &str) -> Result<String> {
+        match self.recorder.pages.get(url) {
+            Some(body) => Ok(body),
+            None => self.recorder.fetch(url),
+        }
+    }
+}
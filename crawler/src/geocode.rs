@@ -0,0 +1,278 @@
+//! 位置参照情報を使ったオフラインのジオコーディング
+//!
+//! 国土交通省の位置参照情報の CSV（街区レベルか大字・町丁目レベル）を読み、
+//! 住所の町名と番地から緯度経度を引く。街区が見つからなければ町丁目の代表点を使う。
+//! 外部のサービスには問い合わせない。
+
+use kanda_model::{Address, Location};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 座標をどこまで絞り込めたか
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+    /// 街区まで一致した
+    Block,
+    /// 町丁目の代表点
+    Town,
+    /// location_overrides.json に書いた座標
+    Override,
+}
+
+/// locations.json の要素
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShopLocation {
+    pub code: usize,
+    pub url: String,
+    pub lat: f64,
+    pub lng: f64,
+    pub precision: Precision,
+}
+
+impl ShopLocation {
+    pub fn location(&self) -> Location {
+        Location {
+            lat: self.lat,
+            lng: self.lng,
+        }
+    }
+}
+
+/// location_overrides.json の要素。位置参照情報で引けない店や、
+/// 引いた座標がずれている店の座標を手で書く
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LocationOverride {
+    pub code: usize,
+    /// 同じコードの店舗が複数あるときに区別する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub lat: f64,
+    pub lng: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// (区市町村, 町丁目) ごとの座標
+#[derive(Default)]
+pub struct Gazetteer {
+    /// 街区符号ごとの座標
+    blocks: HashMap<(String, String), HashMap<String, Location>>,
+    /// 町丁目の代表点
+    towns: HashMap<(String, String), Location>,
+}
+
+impl Gazetteer {
+    pub fn from_csv(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines().enumerate();
+
+        let header = lines
+            .next()
+            .map(|(_, l)| split_row(l))
+            .ok_or("empty file")?;
+        let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+        let muni = column(&["市区町村名"]).ok_or("no 市区町村名 column")?;
+        let town = column(&["大字・丁目名", "大字町丁目名"]).ok_or("no 大字・丁目名 column")?;
+        let block = column(&["街区符号・地番"]);
+        let lat = column(&["緯度"]).ok_or("no 緯度 column")?;
+        let lng = column(&["経度"]).ok_or("no 経度 column")?;
+
+        let mut ret = Self::default();
+        // 街区レベルの CSV なら、町丁目の代表点は街区の平均にする
+        let mut sums = HashMap::<(String, String), (f64, f64, usize)>::new();
+
+        for (i, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let row = split_row(line);
+            let err = || format!("line {}: invalid row: {}", i + 1, line);
+            let get = |ix: usize| row.get(ix).ok_or_else(err);
+
+            let key = (get(muni)?.clone(), get(town)?.clone());
+            let loc = Location {
+                lat: get(lat)?.parse().map_err(|_| err())?,
+                lng: get(lng)?.parse().map_err(|_| err())?,
+            };
+
+            match block {
+                Some(block) => {
+                    ret.blocks
+                        .entry(key.clone())
+                        .or_default()
+                        .insert(get(block)?.clone(), loc);
+                    let e = sums.entry(key).or_insert((0.0, 0.0, 0));
+                    e.0 += loc.lat;
+                    e.1 += loc.lng;
+                    e.2 += 1;
+                }
+                None => {
+                    ret.towns.insert(key, loc);
+                }
+            }
+        }
+
+        for (key, (lat, lng, n)) in sums {
+            ret.towns.entry(key).or_insert(Location {
+                lat: lat / n as f64,
+                lng: lng / n as f64,
+            });
+        }
+
+        Ok(ret)
+    }
+
+    pub fn locate(&self, addr: &Address) -> Option<(Location, Precision)> {
+        let nums = addr.block.split('-').collect::<Vec<_>>();
+        let first = nums[0].parse::<u32>().ok()?;
+
+        for town in town_candidates(&addr.town) {
+            // 「神田小川町3-9」は神田小川町三丁目の 9 番
+            let chome = format!("{}{}丁目", town, kanji_number(first));
+            if let Some(ret) = self.find(&addr.municipality, &chome, nums.get(1).copied()) {
+                return Some(ret);
+            }
+            // 「六番町15」のように丁目のない町
+            if let Some(ret) = self.find(&addr.municipality, &town, Some(nums[0])) {
+                return Some(ret);
+            }
+        }
+
+        None
+    }
+
+    fn find(&self, muni: &str, town: &str, block: Option<&str>) -> Option<(Location, Precision)> {
+        let key = (muni.to_string(), town.to_string());
+
+        if let Some(loc) = block.and_then(|b| self.blocks.get(&key)?.get(b)) {
+            return Some((*loc, Precision::Block));
+        }
+        self.towns.get(&key).map(|loc| (*loc, Precision::Town))
+    }
+}
+
+fn split_row(line: &str) -> Vec<String> {
+    line.split(',')
+        .map(|s| s.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+/// 「神田」を付けたり付けなかったりする町名があるので、両方を試す
+fn town_candidates(town: &str) -> Vec<String> {
+    let mut ret = vec![town.to_string()];
+    match town.strip_prefix("神田") {
+        Some(rest) if !rest.is_empty() => ret.push(rest.to_string()),
+        Some(_) => {}
+        None => ret.push(format!("神田{}", town)),
+    }
+    ret
+}
+
+/// 丁目の漢数字。99 まで
+fn kanji_number(n: u32) -> String {
+    const DIGITS: [&str; 10] = ["", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+
+    match n {
+        0..=9 => DIGITS[n as usize].to_string(),
+        10..=19 => format!("十{}", DIGITS[n as usize % 10]),
+        _ => format!("{}十{}", DIGITS[n as usize / 10], DIGITS[n as usize % 10]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 街区レベルの位置参照情報から抜き出した形
+    const CSV: &str = "\
+\"都道府県名\",\"市区町村名\",\"大字・丁目名\",\"街区符号・地番\",\"緯度\",\"経度\"
+\"東京都\",\"千代田区\",\"神田小川町三丁目\",\"9\",\"35.6950\",\"139.7620\"
+\"東京都\",\"千代田区\",\"神田小川町三丁目\",\"10\",\"35.6960\",\"139.7630\"
+\"東京都\",\"千代田区\",\"三崎町二丁目\",\"22\",\"35.7010\",\"139.7540\"
+\"東京都\",\"千代田区\",\"六番町\",\"15\",\"35.6880\",\"139.7310\"
+\"東京都\",\"千代田区\",\"六番町\",\"7\",\"35.6900\",\"139.7330\"
+";
+
+    fn addr(town: &str, block: &str) -> Address {
+        Address {
+            postal_code: None,
+            prefecture: Some("東京都".to_string()),
+            municipality: "千代田区".to_string(),
+            town: town.to_string(),
+            block: block.to_string(),
+            building: None,
+            floor: None,
+        }
+    }
+
+    fn locate(town: &str, block: &str) -> Option<(f64, f64, Precision)> {
+        let g = Gazetteer::from_csv(CSV).unwrap();
+        g.locate(&addr(town, block))
+            .map(|(loc, p)| (loc.lat, loc.lng, p))
+    }
+
+    #[test]
+    fn chome_town() {
+        assert_eq!(
+            locate("神田小川町", "3-9"),
+            Some((35.6950, 139.7620, Precision::Block))
+        );
+        // 街区がなければ町丁目の代表点（街区の平均）
+        let (lat, lng, p) = locate("神田小川町", "3-1").unwrap();
+        assert!((lat - 35.6955).abs() < 1e-9 && (lng - 139.7625).abs() < 1e-9);
+        assert_eq!(p, Precision::Town);
+    }
+
+    #[test]
+    fn banchi_town() {
+        assert_eq!(
+            locate("六番町", "15"),
+            Some((35.6880, 139.7310, Precision::Block))
+        );
+        assert_eq!(locate("六番町", "1").map(|l| l.2), Some(Precision::Town));
+    }
+
+    #[test]
+    fn kanda_prefix() {
+        // 住所は「神田三崎町」、位置参照情報は「三崎町」
+        assert_eq!(
+            locate("神田三崎町", "2-22-8"),
+            Some((35.7010, 139.7540, Precision::Block))
+        );
+        // 逆に「神田」を付けて探す
+        assert_eq!(
+            locate("小川町", "3-10").map(|l| l.2),
+            Some(Precision::Block)
+        );
+    }
+
+    #[test]
+    fn not_found() {
+        assert_eq!(locate("神保町", "1-1"), None);
+        assert_eq!(locate("神田小川町", "x"), None);
+    }
+
+    #[test]
+    fn candidates() {
+        assert_eq!(town_candidates("神田小川町"), vec!["神田小川町", "小川町"]);
+        assert_eq!(town_candidates("三崎町"), vec!["三崎町", "神田三崎町"]);
+        assert_eq!(town_candidates("神田"), vec!["神田"]);
+    }
+
+    #[test]
+    fn kanji_numbers() {
+        assert_eq!(kanji_number(1), "一");
+        assert_eq!(kanji_number(9), "九");
+        assert_eq!(kanji_number(10), "十");
+        assert_eq!(kanji_number(12), "十二");
+        assert_eq!(kanji_number(20), "二十");
+        assert_eq!(kanji_number(35), "三十五");
+    }
+
+    #[test]
+    fn missing_column() {
+        assert!(Gazetteer::from_csv("市区町村名,緯度,経度\n").is_err());
+    }
+}
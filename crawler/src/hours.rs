@@ -0,0 +1,503 @@
+//! 営業時間の一行の字句解析と構文解析
+//!
+//! 正規化した一行を次の文法で読む。空白は読み飛ばす。
+//!
+//! ```text
+//! line   := item (sep? item)* ("※" 注記)?
+//! item   := header range (sep? range)*
+//! header := (meal | days | "(" meal ")" | "(" days ")" | "(" 注記 ")")* ":"?
+//! days   := day (("・" | "〜")? day)*
+//! range  := time "〜" time lo? ("(" 注記 ")")*
+//! lo     := "(" "LO" lotime? ")" | "LO" lotime
+//! time   := "翌"? (H ":" MM | H "時" (M "分")?)
+//! lotime := time | H ":"?
+//! sep    := "、" | "," | "/"
+//! ```
+
+use kanda_model::{BussinessHours, Time, MEALS};
+use regex::Regex;
+use std::ops::Range;
+
+const WEEKDAYS: &str = "月火水木金土日";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Meal(String),
+    Day(char),
+    Time(Time),
+    /// 「LO 21」のような分のない数
+    Num(usize),
+    /// 翌
+    Next,
+    Tilde,
+    Lo,
+    LParen,
+    RParen,
+    Dot,
+    Colon,
+    Sep,
+    /// ※ から行末まで
+    Note(String),
+    /// どれにも当てはまらない文字の並び
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    /// 元の文字列中の位置（バイト単位）
+    span: Range<usize>,
+}
+
+/// 読めなかった箇所
+#[derive(Debug)]
+pub struct SyntaxError {
+    /// 該当するトークンの位置（バイト単位）
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+fn tokenize(s: &str) -> Vec<Token> {
+    let re = Regex::new(&format!(
+        r"^(?:(?P<ws>\s+)|(?P<time>(?P<th>\d{{1,2}})(?::(?P<tm>\d{{2}})|時(?:(?P<tm2>\d{{1,2}})分)?))|(?P<num>\d+)|(?P<meal>{})(?:タイム)?|(?P<day>[{}祝])|(?P<word>[A-Za-z]+)|(?P<lo>ラストオーダー)|(?P<next>翌)|(?P<tilde>[〜~\-])|(?P<lp>\()|(?P<rp>\))|(?P<dot>・)|(?P<colon>:)|(?P<sep>[、,/])|(?P<note>※.*)|(?P<text>.))",
+        MEALS.join("|"),
+        WEEKDAYS
+    ))
+    .unwrap();
+
+    let mut ret: Vec<Token> = vec![];
+    let mut pos = 0;
+
+    while pos < s.len() {
+        let caps = re.captures(&s[pos..]).unwrap();
+        let m = caps.get(0).unwrap();
+        let span = pos..pos + m.end();
+        pos = span.end;
+
+        let num = |n: &str| caps.name(n).map(|m| m.as_str().parse::<usize>().unwrap());
+
+        let tok = if caps.name("ws").is_some() {
+            continue;
+        } else if caps.name("time").is_some() {
+            Tok::Time(Time::new(
+                num("th").unwrap(),
+                num("tm").or_else(|| num("tm2")).unwrap_or(0),
+            ))
+        } else if let Some(n) = num("num") {
+            Tok::Num(n)
+        } else if let Some(meal) = caps.name("meal") {
+            Tok::Meal(meal.as_str().to_string())
+        } else if let Some(day) = caps.name("day") {
+            Tok::Day(day.as_str().chars().next().unwrap())
+        } else if caps.name("lo").is_some()
+            // 「CLOSE」の LO などを拾わないよう、英単語はまとめて読む
+            || caps.name("word").is_some_and(|w| w.as_str() == "LO")
+        {
+            Tok::Lo
+        } else if caps.name("next").is_some() {
+            Tok::Next
+        } else if caps.name("tilde").is_some() {
+            Tok::Tilde
+        } else if caps.name("lp").is_some() {
+            Tok::LParen
+        } else if caps.name("rp").is_some() {
+            Tok::RParen
+        } else if caps.name("dot").is_some() {
+            Tok::Dot
+        } else if caps.name("colon").is_some() {
+            Tok::Colon
+        } else if caps.name("sep").is_some() {
+            Tok::Sep
+        } else if let Some(note) = caps.name("note") {
+            Tok::Note(note.as_str().to_string())
+        } else {
+            let text = m.as_str();
+            // 続く文字はまとめて一つにする
+            if let Some(Token {
+                tok: Tok::Text(prev),
+                span: prev_span,
+            }) = ret.last_mut()
+            {
+                if prev_span.end == span.start {
+                    prev.push_str(text);
+                    prev_span.end = span.end;
+                    continue;
+                }
+            }
+            Tok::Text(text.to_string())
+        };
+
+        ret.push(Token { tok, span });
+    }
+
+    ret
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    toks: Vec<Token>,
+    i: usize,
+}
+
+/// 曜日と区分
+#[derive(Default)]
+struct Header {
+    meal: Option<String>,
+    days: Vec<char>,
+    notes: Vec<String>,
+}
+
+struct TimeRange {
+    open: Time,
+    close: Time,
+    lo: Option<Time>,
+    notes: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Tok> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, k: usize) -> Option<&Tok> {
+        self.toks.get(self.i + k).map(|t| &t.tok)
+    }
+
+    fn bump(&mut self) -> Option<Tok> {
+        let t = self.toks.get(self.i)?.tok.clone();
+        self.i += 1;
+        Some(t)
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        let span = match self.toks.get(self.i) {
+            Some(t) => t.span.clone(),
+            None => self.s.len()..self.s.len(),
+        };
+        SyntaxError {
+            span,
+            message: message.to_string(),
+        }
+    }
+
+    fn expect(&mut self, tok: Tok, message: &str) -> Result<(), SyntaxError> {
+        if self.peek() == Some(&tok) {
+            self.i += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn skip_seps(&mut self) {
+        while let Some(Tok::Sep) = self.peek() {
+            self.i += 1;
+        }
+    }
+
+    fn line(&mut self) -> Result<Vec<BussinessHours>, SyntaxError> {
+        let mut ret = vec![];
+
+        loop {
+            self.skip_seps();
+            match self.peek() {
+                None => break,
+                Some(Tok::Note(note)) => {
+                    let note = note.clone();
+                    self.i += 1;
+                    for bh in ret.iter_mut() {
+                        add_note(bh, &note);
+                    }
+                }
+                Some(Tok::Text(_)) => return Err(self.error("unexpected text")),
+                Some(_) => ret.append(&mut self.item()?),
+            }
+        }
+
+        if ret.is_empty() {
+            return Err(self.error("expected business hours"));
+        }
+
+        Ok(ret)
+    }
+
+    fn item(&mut self) -> Result<Vec<BussinessHours>, SyntaxError> {
+        let header = self.header()?;
+
+        let mut ranges = vec![self.range()?];
+        loop {
+            let save = self.i;
+            self.skip_seps();
+            if matches!(self.peek(), Some(Tok::Time(_) | Tok::Next)) {
+                ranges.push(self.range()?);
+            } else {
+                self.i = save;
+                break;
+            }
+        }
+
+        let mut ret = vec![];
+
+        for r in ranges {
+            let mut bh = BussinessHours {
+                day_of_week: None,
+                meal: header.meal.clone(),
+                open: r.open,
+                close: r.close,
+                lo: r.lo,
+                from: None,
+                until: None,
+                note: None,
+            };
+            for note in header.notes.iter().chain(&r.notes) {
+                add_note(&mut bh, note);
+            }
+
+            if header.days.is_empty() {
+                ret.push(bh);
+            } else {
+                for d in &header.days {
+                    ret.push(BussinessHours {
+                        day_of_week: Some(d.to_string()),
+                        ..bh.clone()
+                    });
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    fn header(&mut self) -> Result<Header, SyntaxError> {
+        let mut h = Header::default();
+
+        loop {
+            match self.peek() {
+                Some(Tok::Meal(meal)) => {
+                    if h.meal.is_some() {
+                        return Err(self.error("expected time"));
+                    }
+                    h.meal = Some(meal.clone());
+                    self.i += 1;
+                }
+                Some(Tok::Day(_)) => {
+                    let mut days = self.days();
+                    h.days.append(&mut days);
+                }
+                Some(Tok::LParen) => match self.peek_at(1) {
+                    Some(Tok::Meal(meal))
+                        if h.meal.is_none() && self.peek_at(2) == Some(&Tok::RParen) =>
+                    {
+                        h.meal = Some(meal.clone());
+                        self.i += 3;
+                    }
+                    Some(Tok::Day(_)) if self.is_days_paren() => {
+                        self.i += 1;
+                        let mut days = self.days();
+                        h.days.append(&mut days);
+                        self.expect(Tok::RParen, "expected )")?;
+                    }
+                    _ => {
+                        let note = self.paren_note()?;
+                        h.notes.push(note);
+                    }
+                },
+                Some(Tok::Colon) => {
+                    self.i += 1;
+                }
+                _ => break,
+            }
+        }
+
+        dedup(&mut h.days);
+
+        Ok(h)
+    }
+
+    /// 「(月・水)」のように、括弧の中が曜日だけか
+    fn is_days_paren(&self) -> bool {
+        let mut k = 1;
+        loop {
+            match self.peek_at(k) {
+                Some(Tok::Day(_) | Tok::Dot | Tok::Tilde) => k += 1,
+                Some(Tok::RParen) => return k > 1,
+                _ => return false,
+            }
+        }
+    }
+
+    fn days(&mut self) -> Vec<char> {
+        let mut ret = vec![];
+
+        while let Some(Tok::Day(d)) = self.peek() {
+            let d = *d;
+            self.i += 1;
+
+            match (self.peek(), self.peek_at(1)) {
+                (Some(Tok::Tilde), Some(Tok::Day(e))) if d != '祝' && *e != '祝' => {
+                    let e = *e;
+                    self.i += 2;
+                    ret.append(&mut day_range(d, e));
+                }
+                _ => ret.push(d),
+            }
+
+            if let (Some(Tok::Dot), Some(Tok::Day(_))) = (self.peek(), self.peek_at(1)) {
+                self.i += 1;
+            }
+        }
+
+        ret
+    }
+
+    /// 「(」から対応する「)」までを注記として読む
+    fn paren_note(&mut self) -> Result<String, SyntaxError> {
+        let start = self.toks[self.i].span.end;
+        let open = self.i;
+        let mut depth = 0;
+
+        while let Some(tok) = self.bump() {
+            match tok {
+                Tok::LParen => depth += 1,
+                Tok::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let end = self.toks[self.i - 1].span.start;
+                        return Ok(self.s[start..end].trim().to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.i = open;
+        Err(self.error("unclosed ("))
+    }
+
+    fn range(&mut self) -> Result<TimeRange, SyntaxError> {
+        let open = self.time()?;
+        self.expect(Tok::Tilde, "expected 〜")?;
+        let mut close = self.time()?;
+        let mut lo = self.lo(&close)?;
+
+        if close <= open {
+            close.hour += 24;
+        }
+        if let Some(lo) = lo.as_mut() {
+            if *lo < open {
+                lo.hour += 24;
+            }
+        }
+
+        let mut notes = vec![];
+        while self.peek() == Some(&Tok::LParen) && !self.starts_header() {
+            notes.push(self.paren_note()?);
+        }
+
+        Ok(TimeRange {
+            open,
+            close,
+            lo,
+            notes,
+        })
+    }
+
+    /// 括弧が次の項目の曜日や区分か
+    fn starts_header(&self) -> bool {
+        match self.peek_at(1) {
+            Some(Tok::Meal(_)) => self.peek_at(2) == Some(&Tok::RParen),
+            Some(Tok::Day(_)) => self.is_days_paren(),
+            _ => false,
+        }
+    }
+
+    fn time(&mut self) -> Result<Time, SyntaxError> {
+        let next = self.peek() == Some(&Tok::Next);
+        if next {
+            self.i += 1;
+        }
+
+        match self.peek() {
+            Some(Tok::Time(t)) => {
+                let mut t = t.clone();
+                self.i += 1;
+                if next {
+                    t.hour += 24;
+                }
+                Ok(t)
+            }
+            _ => Err(self.error("expected time")),
+        }
+    }
+
+    /// LO。時刻が書かれていなければ `close`
+    fn lo(&mut self, close: &Time) -> Result<Option<Time>, SyntaxError> {
+        match (self.peek(), self.peek_at(1)) {
+            (Some(Tok::LParen), Some(Tok::Lo)) => {
+                self.i += 2;
+                let lo = if self.peek() == Some(&Tok::RParen) {
+                    close.clone()
+                } else {
+                    self.lo_time()?
+                };
+                self.expect(Tok::RParen, "expected )")?;
+                Ok(Some(lo))
+            }
+            (Some(Tok::Lo), _) => {
+                self.i += 1;
+                Ok(Some(self.lo_time()?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn lo_time(&mut self) -> Result<Time, SyntaxError> {
+        if let Some(Tok::Num(h)) = self.peek() {
+            let h = *h;
+            self.i += 1;
+            if self.peek() == Some(&Tok::Colon) {
+                self.i += 1;
+            }
+            return Ok(Time::new(h, 0));
+        }
+        self.time()
+    }
+}
+
+fn add_note(bh: &mut BussinessHours, note: &str) {
+    bh.note = Some(match bh.note.take() {
+        Some(n) => format!("{} {}", n, note),
+        None => note.to_string(),
+    });
+}
+
+/// 「月〜金」を展開する。「金〜月」のように週をまたいでもよい
+fn day_range(from: char, to: char) -> Vec<char> {
+    let days = WEEKDAYS.chars().collect::<Vec<_>>();
+    let i = days.iter().position(|&d| d == from).unwrap();
+    let j = days.iter().position(|&d| d == to).unwrap();
+    let n = (j + 7 - i) % 7 + 1;
+    (0..n).map(|k| days[(i + k) % 7]).collect()
+}
+
+fn dedup(days: &mut Vec<char>) {
+    let mut seen = vec![];
+    days.retain(|d| {
+        if seen.contains(d) {
+            false
+        } else {
+            seen.push(*d);
+            true
+        }
+    });
+}
+
+/// 正規化した一行を読む
+pub fn parse_line(s: &str) -> Result<Vec<BussinessHours>, SyntaxError> {
+    let mut p = Parser {
+        s,
+        toks: tokenize(s),
+        i: 0,
+    };
+    p.line()
+}
@@ -1,16 +1,68 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, ensure, Result};
 use argopt::{cmd_group, subcmd};
+use caveat::collect_caveats;
+use details::{extract_details, extract_menu, img_srcs};
 use easy_scraper::Pattern;
-use regex::Regex;
+use fetch::{Cached, Fetcher, Http, PageDir, Recorder, Replay};
+use geocode::{Gazetteer, LocationOverride, Precision, ShopLocation};
+use kanda_model::{
+    event::EventConfig,
+    holiday::{self, set_calendar, HolidayCalendar},
+    AltBusinessHours, Location, RestaurantInfo,
+};
+use normalize::Normalizer;
+use parser::{parse_address, parse_business_hours, parse_regular_holiday, split_schedules};
+use reconcile::reconcile;
+use sanitize::{apply_overrides, Override};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::{self, File},
-    path::Path,
-    thread,
-    time::Duration,
+    path::{Path, PathBuf},
 };
 
-const STAMP_RARRY_URL: &str = "https://kanda-curry.com/?page_id=12180";
+mod caveat;
+mod details;
+mod fetch;
+mod geocode;
+mod hours;
+mod normalize;
+mod parser;
+mod reconcile;
+mod sanitize;
+
+/// 開催内容の設定ファイル
+const EVENT_CONFIG: &str = "event.json";
+
+/// 取得したページのキャッシュ
+const CACHE_DIR: &str = "cache";
+
+/// geocode で求めた店の緯度経度
+const LOCATIONS: &str = "locations.json";
+
+/// 緯度経度の手作業の修正
+const LOCATION_OVERRIDES: &str = "location_overrides.json";
+
+fn load_event() -> Result<EventConfig> {
+    Ok(serde_json::from_reader(File::open(EVENT_CONFIG)?)?)
+}
+
+/// 手で読み書きするファイル用に、4 スペースで字下げした JSON を書く
+fn write_pretty_json(path: impl AsRef<Path>, value: &impl Serialize) -> Result<()> {
+    let mut buf = vec![];
+    let fmt = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    value.serialize(&mut serde_json::Serializer::with_formatter(&mut buf, fmt))?;
+    fs::write(path, buf)?;
+    Ok(())
+}
+
+/// UTF-8 でなければ Shift_JIS とみなしてテキストファイルを読む
+fn read_text(path: impl AsRef<Path>) -> Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(match String::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(e) => encoding_rs::SHIFT_JIS.decode(e.as_bytes()).0.into_owned(),
+    })
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 struct RestaurantIndex {
@@ -20,7 +72,7 @@ struct RestaurantIndex {
     tn_url: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct RawInfo {
     code: usize,
     name: String,
@@ -30,262 +82,41 @@ struct RawInfo {
     address: String,
     business_hours: String,
     regular_holiday: String,
+    /// 店のページの表の見出しと中身。ページの順に並ぶ
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    table: Vec<(String, String)>,
+    /// カレーの写真
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    menu_image: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct RestaurantInfo {
-    code: usize,
-    name: String,
-    course: String,
-    url: String,
-    tn_url: String,
-    address: String,
-    business_hours: Vec<BussinessHours>,
-    business_hours_raw: String,
-    regular_holiday: Vec<String>,
-    regular_holiday_raw: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct BussinessHours {
-    day_of_week: Option<String>,
-    open: Time,
-    close: Time,
-    lo: Option<Time>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq)]
-struct Time {
-    hour: usize,
-    min: usize,
-}
-
-impl Time {
-    fn new(hour: usize, min: usize) -> Self {
-        Self { hour, min }
-    }
-}
-
-fn parse_business_hours_line(s: &str) -> Option<Vec<BussinessHours>> {
-    // 正規化
-    let s = s.replace("～", "〜");
-    let s = s.replace("（", "(");
-    let s = s.replace("）", ")");
-    let s = s.replace("L.O.", "LO");
-    let s = s.replace("L.O", "LO");
-    let s = s.replace("ＬＯ", "LO");
-    let s = s.replace("平日", "月〜金");
-    let s = s.replace("月曜", "月");
-    let s = s.replace("土曜日", "土");
-    let s = s.replace("土曜", "土");
-    let s = s.replace("から", "〜");
-
-    let s = s.replace("月〜日", "月火水木金土日");
-    let s = s.replace("月〜金", "月火水木金");
-    let s = s.replace("火〜金", "火水木金");
-    let s = s.replace("月〜土", "月火水木金土");
-
-    let s = s.replace("デイナー", "ディナー");
-
-    let s = Regex::new(r"([月火水木金土日祝])・([月火水木金土日祝])")
-        .unwrap()
-        .replace_all(&s, "$1$2");
-    let s = Regex::new(r"([月火水木金土日祝])・([月火水木金土日祝])")
-        .unwrap()
-        .replace_all(&s, "$1$2");
-
-    let s = Regex::new(r"\(([月火水木金土日祝]+)\)")
-        .unwrap()
-        .replace_all(&s, "$1");
-
-    let s = Regex::new(r"(\d{2})[：:](\d{2})")
-        .unwrap()
-        .replace_all(&s, "$1:$2");
-
-    let s = Regex::new(r"([月火水木金土日祝]+) *(ランチ|ディナー|カフェ|バー|モーニング|ティー)")
-        .unwrap()
-        .replace_all(&s, "$2 $1");
-
-    let s = Regex::new(r"、$").unwrap().replace_all(&s, "");
-
-    let s = s.replace("：", "");
-
-    let s = s.trim();
-
-    let re = Regex::new(
-        r#"^(?P<type>カフェ|ランチ|ディナー|バー|モーニング|ティー)? *(?P<day>[月火水木金土日祝]+)? *(?P<open_hour>[0-9]+):(?P<open_min>[0-9]+)〜(?P<close_hour>[0-9]+):(?P<close_min>[0-9]+) *(\((?P<lo>LO) *((?P<lo_hour>[0-9]+):(?P<lo_min>[0-9]+)?)?\))?$"#,
-    )
-    .unwrap();
-
-    let ms = re.captures(s);
-
-    if ms.is_none() {
-        dbg!(s);
-        return None;
-    }
-
-    let ms = ms.unwrap();
-
-    let get_int = |n: &str| -> usize { ms.name(n).unwrap().as_str().parse().unwrap() };
-
-    let mut open = Time::new(get_int("open_hour"), get_int("open_min"));
-    let mut close = Time::new(get_int("close_hour"), get_int("close_min"));
-    let mut lo = if let (Some(h), Some(m)) = (ms.name("lo_hour"), ms.name("lo_min")) {
-        Some(Time::new(
-            h.as_str().parse().unwrap(),
-            m.as_str().parse().unwrap(),
-        ))
-    } else if ms.name("lo").is_some() {
-        Some(close.clone())
-    } else {
-        None
-    };
-
-    if close <= open {
-        close.hour += 24;
-    }
-
-    if let Some(lo) = lo.as_mut() {
-        if lo < &mut open {
-            lo.hour += 24;
-        }
-    }
-
-    let days = ms.name("day");
-
-    let bh = BussinessHours {
-        day_of_week: None,
-        open,
-        close,
-        lo,
-    };
-
-    Some(if let Some(days) = days {
-        days.as_str()
-            .chars()
-            .map(|c| BussinessHours {
-                day_of_week: Some(c.to_string()),
-                ..bh.clone()
-            })
-            .collect()
-    } else {
-        vec![bh]
-    })
-}
-
-fn parse_business_hours(s: &str) -> Vec<BussinessHours> {
-    let s = s
-        .replace("<br>", "\n")
-        .chars()
-        .map(|c| {
-            if c == '\n' || !c.is_whitespace() {
-                c
-            } else {
-                ' '
-            }
-        })
-        .collect::<String>();
-
-    let mut ret = vec![];
-
-    for line in s.lines().filter(|l| !l.is_empty()) {
-        if line.starts_with("※") {
-            continue;
-        }
-
-        if !Regex::new(r"\d[:：]\d").unwrap().is_match(line) {
-            continue;
-        }
-
-        let t = parse_business_hours_line(line);
-        if let Some(mut t) = t {
-            ret.append(&mut t);
-        } else {
-            dbg!(&s);
-            break;
-        }
+/// 保存しておいたページを読むか、実際に取得してそれを保存する
+fn page_fetcher(replay: Option<PathBuf>, cached: bool) -> Box<dyn Fetcher> {
+    match replay {
+        Some(dir) => Box::new(Replay::new(dir)),
+        None if cached => Box::new(Cached::new(Http::default(), CACHE_DIR)),
+        None => Box::new(Recorder::new(Http::default(), CACHE_DIR)),
     }
-
-    ret
 }
 
-fn parse_regular_holiday(s: &str) -> Vec<String> {
-    // 正規化
-    let s = s
-        .replace("<br>", "\n")
-        .chars()
-        .map(|c| {
-            if c == '\n' || !c.is_whitespace() {
-                c
-            } else {
-                ' '
-            }
-        })
-        .collect::<String>();
-
-    let s = s.replace("月曜日", "月");
-    let s = s.replace("火曜日", "火");
-    let s = s.replace("水曜日", "水");
-    let s = s.replace("土曜日", "土");
-    let s = s.replace("日曜日", "日");
-    let s = s.replace("日曜", "日");
-    let s = s.replace("祝日", "祝");
-    let s = s.replace("なし", "年中無休");
-    let s = s.replace("年中無休", "無休");
-    let s = s.replace("・", "");
-    let s = s.replace("、", "");
-    let s = s.replace("ディナー", "");
-
-    // タイポ？
-    let s = s.replace("年始年始", "年末年始");
-
-    let s = s.trim();
-
-    let re = Regex::new(r#"^(([月火水木金土日祝]|年末年始|無休|不定休))+$"#).unwrap();
-
-    let caps = re.captures(s);
-
-    if caps.is_none() {
-        panic!("{:?}", s);
-    }
-
-    let re = Regex::new(
-        r#"^(?P<day>[月火水木金土日祝]+)|(?P<ny>年末年始)|(?P<none>無休)|(?P<unst>不定休)"#,
-    )
-    .unwrap();
-
-    let mut ret = vec![];
-    let mut none = false;
+/// 店舗一覧のページから index.json を作る
+#[subcmd]
+fn get_index(
+    /// 取得する代わりに、このディレクトリに保存されたページを使う
+    #[opt(long)]
+    replay: Option<PathBuf>,
+) -> Result<()> {
+    let event = load_event()?;
 
-    for caps in re.captures_iter(s) {
-        if caps.name("none").is_some() {
-            none = true;
-            continue;
-        }
+    let fetcher = page_fetcher(replay, false);
+    let index = scrape_index(&fetcher.fetch(&event.index_url)?);
 
-        if let Some(day) = caps.name("day") {
-            for c in day.as_str().chars() {
-                ret.push(c.to_string());
-            }
-            continue;
-        }
-
-        if caps.name("ny").is_some() {
-            ret.push("年末年始".to_string());
-            continue;
-        }
-    }
-
-    assert!(if none { ret.is_empty() } else { true });
+    fs::write("index.json", serde_json::to_string(&index)?)?;
 
-    ret
+    Ok(())
 }
 
-#[subcmd]
-fn get_index() -> Result<()> {
-    let resp = ureq::get(STAMP_RARRY_URL).call();
-    let str = resp.into_string()?;
-
+fn scrape_index(html: &str) -> Vec<RestaurantIndex> {
     let pat = Pattern::new(
         r#"
     <div class="container {{course}}line">
@@ -304,9 +135,7 @@ fn get_index() -> Result<()> {
     )
     .unwrap();
 
-    let ms = pat.matches(&str);
-
-    let index = ms
+    pat.matches(html)
         .into_iter()
         .filter(|r| !r["url"].starts_with("#"))
         .map(|r| RestaurantIndex {
@@ -315,98 +144,243 @@ fn get_index() -> Result<()> {
             url: r["url"].to_string(),
             tn_url: r["tn-url"].to_string(),
         })
-        .collect::<Vec<_>>();
-
-    fs::write("index.json", serde_json::to_string(&index)?)?;
-
-    Ok(())
+        .collect()
 }
 
+/// 各店舗のページを取得して raw.json に書き出す
 #[subcmd]
-fn get_data() -> Result<()> {
+fn get_data(
+    /// キャッシュ済みのページは取得し直さない
+    #[opt(long)]
+    resume: bool,
+    /// キャッシュを使わずにすべてのページを取得し直す
+    #[opt(long)]
+    refresh: bool,
+    /// 取得する代わりに、このディレクトリに保存されたページを使う
+    #[opt(long)]
+    replay: Option<PathBuf>,
+) -> Result<()> {
+    // 取得したページは cache/ に保存しておき、途中で失敗したら --resume で続きから取得できる。
+    // cache/ は --replay に渡せば、ネットワークなしで同じ結果を作れる。
+    ensure!(
+        !(resume && refresh),
+        "--resume and --refresh cannot be used together"
+    );
+
     let index: Vec<RestaurantIndex> = serde_json::from_reader(File::open("index.json")?)?;
 
-    assert_eq!(index.len(), 100);
+    let event = load_event()?;
+
+    ensure!(
+        index.len() == event.shop_count,
+        "index.json has {} shops, but {} expects {}",
+        index.len(),
+        EVENT_CONFIG,
+        event.shop_count
+    );
+
+    if replay.is_none() && !resume && !refresh {
+        let cache = PageDir::new(CACHE_DIR);
+        ensure!(
+            index.iter().all(|ix| !cache.contains(&ix.url)),
+            "{}/ already has some pages; use --resume to continue or --refresh to get them again",
+            CACHE_DIR
+        );
+    }
 
-    let pat = Pattern::new(
-        r#"
-    <table class="hyou" subseq>
-        <tr><th>店名</th><td>{{name:*}}</td></tr>
-        <tr><th>住所</th><td>{{address:*}}</td></tr>
-        <tr><th>営業時間</th><td>{{business-hours:*}}</td></tr>
-        <tr><th>定休日</th><td>{{regular-holiday:*}}</td></tr>
-        <tr><th>カレーグランプリ店舗コード</th><td>{{code}}</td></tr>
-    </table>
-    "#,
-    )
-    .unwrap();
+    let fetcher = page_fetcher(replay, !refresh);
 
     let n = index.len();
 
+    fs::create_dir_all("data")?;
+
     let mut json = vec![];
+    let mut failed = vec![];
 
     for (i, ix) in index.into_iter().enumerate() {
         eprintln!("[{}/{}]: getting info: {}", i + 1, n, ix.url);
 
-        let resp = ureq::get(&ix.url).call();
-        if let Some(err) = resp.synthetic_error() {
-            eprintln!("failed to get: {}, {:?}", ix.url, err);
-            continue;
+        let info = match fetcher
+            .fetch(&ix.url)
+            .and_then(|html| scrape_shop(&html, &ix))
+        {
+            Ok(info) => info,
+            Err(err) => {
+                eprintln!("{:#}", err);
+                failed.push(ix.url);
+                continue;
+            }
+        };
+
+        let fname = format!("data/{}.json", info.code);
+        fs::write(&fname, serde_json::to_string(&info)?)?;
+        eprintln!("saved to {}", &fname);
+
+        json.push(info);
+    }
+
+    if !failed.is_empty() {
+        for url in &failed {
+            eprintln!("failed: {}", url);
         }
+        bail!(
+            "failed to get {} of {} shops; run again with --resume",
+            failed.len(),
+            n
+        );
+    }
 
-        let str = resp.into_string()?;
+    fs::write("raw.json", serde_json::to_string(&json)?)?;
 
-        let ms = pat.matches(&str);
-        assert!(ms.len() >= 1);
+    Ok(())
+}
 
-        let info = &ms[0];
+fn scrape_shop(html: &str, ix: &RestaurantIndex) -> Result<RawInfo> {
+    // 行の並びや増減は店によって違うので、見出しと中身の組をすべて取っておく
+    let pat = Pattern::new(
+        r#"
+    <table class="hyou">
+        <tr><th>{{key:*}}</th><td>{{value:*}}</td></tr>
+    </table>
+    "#,
+    )
+    .unwrap();
 
-        let info = RawInfo {
-            code: info["code"].parse().unwrap(),
-            name: info["name"].to_string(),
-            course: ix.course.clone(),
-            url: ix.url.clone(),
-            tn_url: ix.tn_url.clone(),
-            address: info["address"].to_string(),
-            business_hours: info["business-hours"].to_string(),
-            regular_holiday: info["regular-holiday"].to_string(),
-        };
+    let table = pat
+        .matches(html)
+        .into_iter()
+        .map(|m| (m["key"].trim().to_string(), m["value"].trim().to_string()))
+        .collect::<Vec<_>>();
 
-        let fname = format!("data/{}.json", info.code);
-        if Path::new(&fname).exists() {
-            panic!("File {} is already exists", fname);
-        }
+    let get = |key: &str| {
+        table
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.to_string())
+            .ok_or_else(|| anyhow!("{}: {} not found", ix.url, key))
+    };
 
-        eprintln!("saved to {}", &fname);
+    let code = get("カレーグランプリ店舗コード")?;
 
-        fs::write(&fname, serde_json::to_string(&info)?)?;
-        json.push(info);
-        thread::sleep(Duration::from_millis(1000));
-    }
+    // 表の外に載っているカレーの写真。一覧のサムネイルは除く
+    let menu_image = img_srcs(html)
+        .into_iter()
+        .find(|src| src.contains("/wp-content/uploads/") && *src != ix.tn_url);
+
+    Ok(RawInfo {
+        code: code
+            .parse()
+            .map_err(|_| anyhow!("{}: invalid code: {}", ix.url, code))?,
+        name: get("店名")?,
+        course: ix.course.clone(),
+        url: ix.url.clone(),
+        tn_url: ix.tn_url.clone(),
+        address: get("住所")?,
+        business_hours: get("営業時間")?,
+        regular_holiday: get("定休日")?,
+        table,
+        menu_image,
+    })
+}
+
+/// raw.json に overrides.json の修正を当てて sanitized.json を作る
+#[subcmd]
+fn sanitize() -> Result<()> {
+    let raws: Vec<RawInfo> = serde_json::from_reader(File::open("raw.json")?)?;
+    let overrides: Vec<Override> = serde_json::from_reader(File::open("overrides.json")?)?;
 
-    assert_eq!(json.len(), n);
+    let (sanitized, warnings) = apply_overrides(&raws, &overrides);
+    for w in &warnings {
+        eprintln!("{}", w);
+    }
 
-    fs::write("info.json", serde_json::to_string(&json)?)?;
+    write_pretty_json("sanitized.json", &sanitized)?;
 
     Ok(())
 }
 
+/// sanitized.json の営業時間や定休日、住所を読んで info.json を作る
 #[subcmd]
 fn parse() -> Result<()> {
     let infos: Vec<RawInfo> = serde_json::from_reader(File::open("sanitized.json")?)?;
     let raw_infos: Vec<RawInfo> = serde_json::from_reader(File::open("raw.json")?)?;
 
-    let mut parsed = vec![];
+    let norm = Normalizer::load("normalize.json")?;
 
-    for info in infos {
-        let raw = raw_infos.iter().find(|r| r.name == info.name).unwrap();
+    // geocode を一度も実行していなければ位置はなし
+    let locations: Vec<ShopLocation> = if Path::new(LOCATIONS).exists() {
+        serde_json::from_reader(File::open(LOCATIONS)?)?
+    } else {
+        vec![]
+    };
+
+    let (raws, notes) = reconcile(&infos, &raw_infos);
+    for note in &notes {
+        eprintln!("{}", note);
+    }
+    let raws = raws
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("some shops in sanitized.json are not found in raw.json"))?;
+
+    // 表の行を保存する前の get-data で作った raw.json だと、電話番号やメニューが空になる
+    if raws.iter().all(|raw| raw.table.is_empty()) {
+        eprintln!(
+            "warning: raw.json has no table rows; run get-data --refresh to get details and menus"
+        );
+    }
 
-        let bh = parse_business_hours(&info.business_hours);
+    let mut parsed = vec![];
+    let mut errors = vec![];
+
+    for (info, raw) in infos.iter().zip(raws) {
+        let mut fired = vec![];
+
+        let (bh, alt_bh) = split_schedules(&info.business_hours);
+
+        let bh =
+            parse_business_hours(info.code, &bh, &norm, &mut fired).unwrap_or_else(|mut es| {
+                errors.append(&mut es);
+                vec![]
+            });
+        let alt_bh = alt_bh.map(|s| AltBusinessHours {
+            label: "通常の営業時間".to_string(),
+            business_hours: parse_business_hours(info.code, &s, &norm, &mut fired).unwrap_or_else(
+                |mut es| {
+                    errors.append(&mut es);
+                    vec![]
+                },
+            ),
+        });
         let raw_bh = raw.business_hours.clone();
 
-        let rh = parse_regular_holiday(&info.regular_holiday);
+        let caveats = collect_caveats(
+            &info.business_hours,
+            &bh.iter()
+                .chain(alt_bh.iter().flat_map(|alt| &alt.business_hours))
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+
+        let (rh, irregular) =
+            parse_regular_holiday(info.code, &info.regular_holiday, &norm, &mut fired)
+                .unwrap_or_else(|e| {
+                    errors.push(e);
+                    (vec![], false)
+                });
         let raw_rh = raw.regular_holiday.clone();
 
+        let address = match parse_address(info.code, &info.address, &norm, &mut fired) {
+            Ok(address) => Some(address),
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+
+        let details = extract_details(&raw.table);
+        let menu = extract_menu(&raw.table, raw.menu_image.as_deref());
+
         let dat = RestaurantInfo {
             code: info.code,
             name: info.name.clone(),
@@ -414,19 +388,275 @@ fn parse() -> Result<()> {
             url: info.url.clone(),
             tn_url: info.tn_url.clone(),
             address: info.address.clone(),
+            address_parts: address,
             business_hours: bh,
+            business_hours_label: alt_bh.as_ref().map(|_| "当面の営業時間".to_string()),
+            alt_business_hours: alt_bh,
             business_hours_raw: raw_bh,
             regular_holiday: rh,
+            irregular_holiday: irregular,
             regular_holiday_raw: raw_rh,
+            caveats,
+            normalize_rules: fired,
+            phone: details.phone,
+            seats: details.seats,
+            smoking: details.smoking,
+            links: details.links,
+            menu,
+            location: locations
+                .iter()
+                .find(|l| l.code == info.code && l.url == info.url)
+                .map(|l| l.location()),
         };
 
         parsed.push(dat);
     }
 
+    if !errors.is_empty() {
+        for err in &errors {
+            eprintln!("{}", err);
+        }
+        bail!("{} parse errors", errors.len());
+    }
+
     fs::write("info.json", serde_json::to_string(&parsed)?)?;
 
     Ok(())
 }
 
-#[cmd_group(verbose, commands = [get_index, get_data, parse])]
+/// info.json の住所から緯度経度を求め、locations.json と info.json に書く
+#[subcmd]
+fn geocode(
+    /// 国土交通省の位置参照情報の CSV（街区レベルか大字・町丁目レベル）
+    csv: PathBuf,
+) -> Result<()> {
+    let gazetteer =
+        Gazetteer::from_csv(&read_text(&csv)?).map_err(|e| anyhow!("{}: {}", csv.display(), e))?;
+    let overrides: Vec<LocationOverride> = if Path::new(LOCATION_OVERRIDES).exists() {
+        serde_json::from_reader(File::open(LOCATION_OVERRIDES)?)?
+    } else {
+        vec![]
+    };
+
+    let mut infos: Vec<RestaurantInfo> = serde_json::from_reader(File::open("info.json")?)?;
+    let mut locations = vec![];
+    let mut missing = 0;
+
+    for info in infos.iter_mut() {
+        // location_overrides.json に書いた座標を優先する
+        let ov = overrides
+            .iter()
+            .find(|ov| ov.code == info.code && ov.url.as_ref().is_none_or(|url| *url == info.url));
+
+        let found = match ov {
+            Some(ov) => Some((
+                Location {
+                    lat: ov.lat,
+                    lng: ov.lng,
+                },
+                Precision::Override,
+            )),
+            None => info
+                .address_parts
+                .as_ref()
+                .and_then(|addr| gazetteer.locate(addr)),
+        };
+
+        info.location = found.map(|(loc, _)| loc);
+
+        match found {
+            Some((loc, precision)) => {
+                if precision == Precision::Town {
+                    eprintln!(
+                        "[{}] {}: block not found, using the town",
+                        info.code, info.address
+                    );
+                }
+                locations.push(ShopLocation {
+                    code: info.code,
+                    url: info.url.clone(),
+                    lat: loc.lat,
+                    lng: loc.lng,
+                    precision,
+                });
+            }
+            None => {
+                eprintln!("[{}] {}: not found", info.code, info.address);
+                missing += 1;
+            }
+        }
+    }
+
+    write_pretty_json(LOCATIONS, &locations)?;
+    fs::write("info.json", serde_json::to_string(&infos)?)?;
+
+    if missing > 0 {
+        eprintln!(
+            "{} shops not found; add them to {}",
+            missing, LOCATION_OVERRIDES
+        );
+    }
+
+    Ok(())
+}
+
+/// 指定した年の祝日を holidays.json に書き出す
+#[subcmd]
+fn holidays(
+    /// 内閣府の syukujitsu.csv。含まれる年は計算結果の代わりにこれを使う
+    #[opt(long)]
+    csv: Option<PathBuf>,
+    years: Vec<i32>,
+) -> Result<()> {
+    if let Some(csv) = csv {
+        // 内閣府の CSV は Shift_JIS
+        let s = read_text(&csv)?;
+        set_calendar(
+            HolidayCalendar::from_csv(&s).map_err(|e| anyhow!("{}: {}", csv.display(), e))?,
+        );
+    }
+
+    let mut hs = vec![];
+
+    for year in years {
+        for h in holiday::holidays(year) {
+            eprintln!("{} {}", h.date, h.name);
+            hs.push(h);
+        }
+    }
+
+    fs::write("holidays.json", serde_json::to_string(&hs)?)?;
+
+    Ok(())
+}
+
+#[cmd_group(verbose, commands = [get_index, get_data, sanitize, parse, geocode, holidays])]
 fn main() -> Result<()> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// tests/pages は raw.json の内容をもとに組み立てた合成のページで、
+    /// 実際のサイトから保存したものではない（tests/pages/README.md）。
+    /// `get-data` で保存した実際のページに差し替えてもそのまま読める
+    fn replay() -> Replay {
+        Replay::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/pages"))
+    }
+
+    const INDEX_URL: &str = "https://kanda-curry.com/?page_id=12180";
+
+    fn index() -> Vec<RestaurantIndex> {
+        scrape_index(&replay().fetch(INDEX_URL).unwrap())
+    }
+
+    fn shop(url: &str) -> RawInfo {
+        let ix = index().into_iter().find(|ix| ix.url == url).unwrap();
+        scrape_shop(&replay().fetch(url).unwrap(), &ix).unwrap()
+    }
+
+    #[test]
+    fn replay_index() {
+        let index = index();
+        assert_eq!(
+            index
+                .iter()
+                .map(|ix| (ix.course.as_str(), ix.url.as_str(), ix.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "A",
+                    "https://kanda-curry.com/?p=6",
+                    "欧風カレー ボンディ 神田小川町店"
+                ),
+                (
+                    "A",
+                    "https://kanda-curry.com/?p=6807",
+                    "Café & Bar BASE CAMP"
+                ),
+                ("B", "https://kanda-curry.com/?p=4882", "日乃屋カレー九段下"),
+            ]
+        );
+        assert_eq!(
+            index[0].tn_url,
+            "https://kanda-curry.com/wp/wp-content/uploads/2018/08/s2887.jpg"
+        );
+    }
+
+    #[test]
+    fn replay_shop() {
+        let info = shop("https://kanda-curry.com/?p=6807");
+        assert_eq!(
+            json!([
+                info.code,
+                info.name,
+                info.course,
+                info.address,
+                info.business_hours,
+                info.regular_holiday,
+                info.menu_image,
+            ]),
+            json!([
+                1701,
+                "Cafe&amp;Bar BASE CAMP",
+                "A",
+                "〒101-0061<br>東京都千代田区三崎町2-22-8　梨本ビル1F",
+                "火～金　11：30～23：30（LO23：00）<br>土・祝　15：00～23：30（LO23：00）",
+                "日・月、年末年始",
+                "https://kanda-curry.com/wp/wp-content/uploads/2020/08/basecamp-curry.jpg",
+            ])
+        );
+        assert_eq!(info.table.len(), 5);
+
+        // 一覧のサムネイルと同じ写真はカレーの写真にしない
+        assert_eq!(shop("https://kanda-curry.com/?p=4882").menu_image, None);
+    }
+
+    // ?p=6 の詳細とメニューの行は架空の値。実際のページのマークアップに
+    // 合っているかは、保存した実際のページで確かめる必要がある
+    #[test]
+    fn shop_details() {
+        let info = shop("https://kanda-curry.com/?p=6");
+        let details = extract_details(&info.table);
+        assert_eq!(
+            json!([details.phone, details.seats, details.smoking, details.links]),
+            json!([
+                "03-1234-5678",
+                30,
+                "no_smoking",
+                [{ "label": "公式サイト", "url": "https://example.com/bondy/" }],
+            ])
+        );
+
+        // 表に行のない店は空のまま
+        let details = extract_details(&shop("https://kanda-curry.com/?p=6807").table);
+        assert_eq!(
+            json!([details.phone, details.seats, details.smoking, details.links]),
+            json!([null, null, null, []])
+        );
+    }
+
+    #[test]
+    fn shop_menu() {
+        let info = shop("https://kanda-curry.com/?p=6");
+        let menu = extract_menu(&info.table, info.menu_image.as_deref());
+        assert_eq!(
+            serde_json::to_value(menu).unwrap(),
+            json!({
+                "name": "ビーフカレー",
+                "price": 1500,
+                "description": "じっくり煮込んだ 欧風カレーです。",
+                "image": "https://kanda-curry.com/wp/wp-content/uploads/2020/08/bondy-curry.jpg",
+            })
+        );
+
+        let info = shop("https://kanda-curry.com/?p=6807");
+        assert!(extract_menu(&info.table, info.menu_image.as_deref()).is_none());
+    }
+
+    #[test]
+    fn replay_missing_page() {
+        assert!(replay().fetch("https://kanda-curry.com/?p=0").is_err());
+    }
+}
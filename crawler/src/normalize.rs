@@ -0,0 +1,226 @@
+//! 営業時間、定休日、住所の表記ゆれの正規化
+//!
+//! NFKC で全角・半角をそろえたあと、normalize.json のルールを上から順に当てる。
+//! 新しい書き方が出てきたら、ここを変更せずにルールを足せばよい。
+
+use crate::parser::Field;
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::{fs::File, path::Path};
+use unicode_normalization::UnicodeNormalization;
+
+/// NFKC で文字列が変わったときに記録する名前
+pub const NFKC: &str = "NFKC";
+
+#[derive(Deserialize, Debug)]
+struct RuleDef {
+    name: String,
+    /// 当てる項目。配列で複数書ける。省略するとすべての項目
+    #[serde(default)]
+    field: Option<Fields>,
+    from: String,
+    to: String,
+    /// `from` を正規表現とみなす。`to` では `$1` などが使える
+    #[serde(default)]
+    regex: bool,
+    /// 変わらなくなるまで繰り返す
+    #[serde(default)]
+    repeat: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Fields {
+    One(Field),
+    Many(Vec<Field>),
+}
+
+impl Fields {
+    fn into_vec(self) -> Vec<Field> {
+        match self {
+            Fields::One(f) => vec![f],
+            Fields::Many(fs) => fs,
+        }
+    }
+}
+
+struct Rule {
+    name: String,
+    /// `None` ならすべての項目
+    fields: Option<Vec<Field>>,
+    from: Pattern,
+    to: String,
+    repeat: bool,
+}
+
+enum Pattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Rule {
+    fn apply(&self, s: &str) -> String {
+        match &self.from {
+            Pattern::Literal(from) => s.replace(from.as_str(), &self.to),
+            Pattern::Regex(re) => re.replace_all(s, self.to.as_str()).into_owned(),
+        }
+    }
+}
+
+pub struct Normalizer {
+    rules: Vec<Rule>,
+}
+
+impl Normalizer {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let defs: Vec<RuleDef> = serde_json::from_reader(File::open(path)?)?;
+        Self::from_defs(defs, path)
+    }
+
+    fn from_defs(defs: Vec<RuleDef>, path: &Path) -> Result<Self> {
+        let rules = defs
+            .into_iter()
+            .map(|def| {
+                let from = if def.regex {
+                    Pattern::Regex(
+                        Regex::new(&def.from)
+                            .map_err(|e| anyhow!("{}: rule {}: {}", path.display(), def.name, e))?,
+                    )
+                } else {
+                    Pattern::Literal(def.from)
+                };
+                Ok(Rule {
+                    name: def.name,
+                    fields: def.field.map(Fields::into_vec),
+                    from,
+                    to: def.to,
+                    repeat: def.repeat,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// `field` の文字列 `s` を正規化する。文字列を変えたルールの名前を `fired` に足す
+    pub fn apply(&self, field: Field, s: &str, fired: &mut Vec<String>) -> String {
+        let t = s.nfkc().collect::<String>();
+        fire(fired, NFKC, t != s);
+        let mut s = t;
+
+        for rule in &self.rules {
+            if rule.fields.as_ref().is_some_and(|fs| !fs.contains(&field)) {
+                continue;
+            }
+
+            loop {
+                let t = rule.apply(&s);
+                let changed = t != s;
+                fire(fired, &rule.name, changed);
+                s = t;
+                if !rule.repeat || !changed {
+                    break;
+                }
+            }
+        }
+
+        s
+    }
+}
+
+fn fire(fired: &mut Vec<String>, name: &str, changed: bool) {
+    if changed && !fired.iter().any(|n| n == name) {
+        fired.push(name.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalizer(json: &str) -> Normalizer {
+        Normalizer::from_defs(serde_json::from_str(json).unwrap(), Path::new("test")).unwrap()
+    }
+
+    fn apply(n: &Normalizer, field: Field, s: &str) -> (String, Vec<String>) {
+        let mut fired = vec![];
+        let s = n.apply(field, s, &mut fired);
+        (s, fired)
+    }
+
+    #[test]
+    fn field_filter() {
+        let n = normalizer(
+            r#"[
+                {"name": "すべて", "from": "a", "to": "b"},
+                {"name": "営業時間", "field": "business_hours", "from": "c", "to": "d"},
+                {"name": "曜日", "field": ["business_hours", "regular_holiday"], "from": "e", "to": "f"}
+            ]"#,
+        );
+        assert_eq!(
+            apply(&n, Field::BusinessHours, "ace"),
+            (
+                "bdf".to_string(),
+                vec![
+                    "すべて".to_string(),
+                    "営業時間".to_string(),
+                    "曜日".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            apply(&n, Field::RegularHoliday, "ace"),
+            (
+                "bcf".to_string(),
+                vec!["すべて".to_string(), "曜日".to_string()]
+            )
+        );
+        assert_eq!(
+            apply(&n, Field::Address, "ace"),
+            ("bce".to_string(), vec!["すべて".to_string()])
+        );
+    }
+
+    #[test]
+    fn fired_names() {
+        let n = normalizer(
+            r#"[
+                {"name": "中黒", "from": "(\\w)・(\\w)", "to": "$1$2", "regex": true, "repeat": true},
+                {"name": "使われない", "from": "x", "to": "y"}
+            ]"#,
+        );
+        // 変えなかったルールは記録しない。繰り返しても名前は一度だけ
+        assert_eq!(
+            apply(&n, Field::BusinessHours, "月・水・金"),
+            ("月水金".to_string(), vec!["中黒".to_string()])
+        );
+        // NFKC で変われば、それも記録する
+        assert_eq!(
+            apply(&n, Field::BusinessHours, "月･水"),
+            (
+                "月水".to_string(),
+                vec![NFKC.to_string(), "中黒".to_string()]
+            )
+        );
+        assert_eq!(
+            apply(&n, Field::BusinessHours, "月"),
+            ("月".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn bad_regex() {
+        let defs =
+            serde_json::from_str(r#"[{"name": "壊れた", "from": "(", "to": "", "regex": true}]"#);
+        let err = Normalizer::from_defs(defs.unwrap(), Path::new("test.json"))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().starts_with("test.json: rule 壊れた:"),
+            "{}",
+            err
+        );
+    }
+}
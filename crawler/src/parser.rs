@@ -0,0 +1,756 @@
+use crate::{hours, normalize::Normalizer};
+use chrono::{Datelike, NaiveDate};
+use kanda_model::{Address, BussinessHours, HolidayRule, MonthDay, Time, MEALS};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::fmt;
+
+/// パースに失敗した箇所
+#[derive(Debug)]
+pub struct ParseError {
+    pub code: usize,
+    pub field: Field,
+    /// 元データの該当行
+    pub line: String,
+    /// 正規化後の行
+    pub normalized: String,
+    /// `normalized` 中で読めなくなった位置（文字単位）
+    pub pos: usize,
+    /// 読めなかったトークンの長さ（文字単位）
+    pub len: usize,
+    pub message: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    BusinessHours,
+    RegularHoliday,
+    Address,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Field::BusinessHours => write!(f, "営業時間"),
+            Field::RegularHoliday => write!(f, "定休日"),
+            Field::Address => write!(f, "住所"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (read, token, rest): (String, String, String) = {
+            let mut cs = self.normalized.chars();
+            let read = cs.by_ref().take(self.pos).collect();
+            let token = cs.by_ref().take(self.len).collect();
+            (read, token, cs.collect())
+        };
+
+        writeln!(f, "[{}] {}: {}", self.code, self.field, self.line)?;
+        writeln!(f, "    {}: {} ▶ {}{}", self.pos, read, token, rest)?;
+        write!(f, "    {}: {:?}", self.message, token)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 正規化後の文字列と、読めなかった位置（バイト単位）
+struct Mismatch {
+    normalized: String,
+    pos: usize,
+    len: usize,
+    message: String,
+}
+
+impl Mismatch {
+    fn into_error(self, code: usize, field: Field, line: &str) -> ParseError {
+        ParseError {
+            code,
+            field,
+            line: line.to_string(),
+            pos: self.normalized[..self.pos].chars().count(),
+            len: self.normalized[self.pos..self.pos + self.len]
+                .chars()
+                .count(),
+            message: self.message,
+            normalized: self.normalized,
+        }
+    }
+}
+
+/// 先頭から順に正規表現で読み進める
+struct Scanner<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(s: &'a str) -> Self {
+        Self { s, pos: 0 }
+    }
+
+    fn eat(&mut self, re: &str) -> Option<Captures<'a>> {
+        let re = Regex::new(&format!("^(?:{})", re)).unwrap();
+        let caps = re.captures(&self.s[self.pos..])?;
+        self.pos += caps.get(0).unwrap().end();
+        Some(caps)
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos == self.s.len()
+    }
+
+    fn mismatch(&self, message: &str) -> Mismatch {
+        Mismatch {
+            normalized: self.s.to_string(),
+            pos: self.pos,
+            len: 0,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn parse_business_hours_line(
+    s: &str,
+    norm: &Normalizer,
+    fired: &mut Vec<String>,
+) -> Result<Vec<BussinessHours>, Mismatch> {
+    let s = norm.apply(Field::BusinessHours, s, fired);
+    let s = s.trim();
+
+    hours::parse_line(s).map_err(|e| Mismatch {
+        normalized: s.to_string(),
+        pos: e.span.start,
+        len: e.span.len(),
+        message: e.message,
+    })
+}
+
+/// 当面の営業時間と通常の営業時間が併記されていたら、その二つに分ける
+///
+/// 「通常の営業時間は以下の通り」のような行より後ろ、
+/// または「（平常時：〜）」の行を通常の営業時間とみなす。
+pub fn split_schedules(s: &str) -> (String, Option<String>) {
+    let s = s.replace("\n", "<br>");
+    let lines = s.split("<br>").collect::<Vec<_>>();
+
+    let intro = Regex::new(r"(ガイドブック|通常).*(以下|下記)").unwrap();
+
+    if let Some(i) = lines.iter().position(|l| intro.is_match(l)) {
+        return (lines[..i].join("<br>"), Some(lines[i + 1..].join("<br>")));
+    }
+
+    let inline = Regex::new(r"^[(（]?平常時[:：](?P<hours>.+?)[)）]?$").unwrap();
+
+    if let Some(i) = lines.iter().position(|l| inline.is_match(l)) {
+        let alt = inline.captures(lines[i]).unwrap()["hours"].to_string();
+        let rest = lines
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, l)| *l)
+            .collect::<Vec<_>>();
+        return (rest.join("<br>"), Some(alt));
+    }
+
+    (s, None)
+}
+
+pub fn parse_business_hours(
+    code: usize,
+    s: &str,
+    norm: &Normalizer,
+    fired: &mut Vec<String>,
+) -> Result<Vec<BussinessHours>, Vec<ParseError>> {
+    let s = s
+        .replace("<br>", "\n")
+        .chars()
+        .map(|c| {
+            if c == '\n' || !c.is_whitespace() {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>();
+
+    let time_re = Regex::new(r"\d[:：]\d").unwrap();
+
+    let mut ret = vec![];
+    let mut errs = vec![];
+
+    for line in s.lines().filter(|l| !l.is_empty()) {
+        if line.starts_with("※") {
+            continue;
+        }
+
+        if !time_re.is_match(line) {
+            continue;
+        }
+
+        match parse_business_hours_line(line, norm, fired) {
+            Ok(mut t) => ret.append(&mut t),
+            Err(m) => errs.push(m.into_error(code, Field::BusinessHours, line)),
+        }
+    }
+
+    apply_periods(&s, &mut ret);
+
+    if errs.is_empty() {
+        Ok(ret)
+    } else {
+        Err(errs)
+    }
+}
+
+/// 「9月はランチのみ」「ディナータイムは、8/31まで22:00（LO21:30）まで」
+/// のような期間限定の営業時間を反映する
+///
+/// 「9月はランチのみ」なら、ランチは通年のまま、ほかの区分の営業時間を 9 月以外にする。
+fn apply_periods(s: &str, bhs: &mut Vec<BussinessHours>) {
+    let month = Regex::new(&format!(
+        r"(?P<month>[0-9]+)月は(?P<meal>{})のみ",
+        MEALS.join("|")
+    ))
+    .unwrap();
+
+    let only = month.captures(s).and_then(|caps| {
+        let m = caps["month"]
+            .parse::<u32>()
+            .ok()
+            .filter(|m| (1..=12).contains(m))?;
+        Some((m, caps["meal"].to_string()))
+    });
+
+    if let Some((m, meal)) = only {
+        let prev = (m + 10) % 12 + 1;
+        for bh in bhs
+            .iter_mut()
+            .filter(|bh| bh.meal.as_ref().is_some_and(|x| *x != meal))
+        {
+            // 翌月の 1 日から前月の末日まで
+            bh.from = Some(MonthDay::new(m % 12 + 1, 1));
+            bh.until = Some(MonthDay::new(prev, last_day(prev)));
+        }
+    }
+
+    let until = Regex::new(
+        r"(?P<meal>ランチ|ディナー)タイムは、?(?P<month>[0-9]+)/(?P<day>[0-9]+)まで(?P<close_hour>[0-9]+)[:：](?P<close_min>[0-9]+)(?:[(（]LO ?(?P<lo_hour>[0-9]+)[:：](?P<lo_min>[0-9]+)[)）])?まで",
+    )
+    .unwrap();
+
+    for caps in until.captures_iter(s) {
+        let get_int =
+            |n: &str| -> Option<usize> { caps.name(n).map(|m| m.as_str().parse().unwrap()) };
+
+        let last = MonthDay::new(get_int("month").unwrap() as _, get_int("day").unwrap() as _);
+        let close = Time::new(
+            get_int("close_hour").unwrap(),
+            get_int("close_min").unwrap(),
+        );
+        let lo = get_int("lo_hour").map(|h| Time::new(h, get_int("lo_min").unwrap()));
+
+        let mut shortened = vec![];
+
+        for bh in bhs
+            .iter_mut()
+            .filter(|bh| bh.meal.as_deref() == Some(&caps["meal"]))
+        {
+            let mut close = close.clone();
+            if close <= bh.open {
+                close.hour += 24;
+            }
+
+            shortened.push(BussinessHours {
+                close,
+                lo: lo.clone(),
+                until: Some(last),
+                ..bh.clone()
+            });
+            bh.from = Some(last.succ());
+        }
+
+        bhs.append(&mut shortened);
+    }
+}
+
+/// `month` の末日。2 月は 29 日
+fn last_day(month: u32) -> u32 {
+    NaiveDate::from_ymd_opt(2000, month % 12 + 1, 1)
+        .and_then(|d| d.pred_opt())
+        .unwrap()
+        .day()
+}
+
+/// 「第2」「第三」の数字
+fn nth_number(c: char) -> Option<u32> {
+    match "一二三四五".chars().position(|k| k == c) {
+        Some(i) => Some(i as u32 + 1),
+        None => c.to_digit(10),
+    }
+}
+
+/// 定休日と、不定休があるかどうか
+pub fn parse_regular_holiday(
+    code: usize,
+    s: &str,
+    norm: &Normalizer,
+    fired: &mut Vec<String>,
+) -> Result<(Vec<HolidayRule>, bool), ParseError> {
+    let line = s;
+
+    // 正規化
+    let s = s
+        .replace("<br>", "\n")
+        .chars()
+        .map(|c| {
+            if c == '\n' || !c.is_whitespace() {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>();
+
+    let s = norm.apply(Field::RegularHoliday, &s, fired);
+
+    let s = s.trim();
+
+    let mut sc = Scanner::new(s);
+
+    let mut ret = vec![];
+    let mut none = false;
+    let mut irregular = false;
+
+    loop {
+        sc.eat(r"[\s・、,]+");
+        if sc.is_eof() {
+            break;
+        }
+
+        let caps = sc
+            .eat(
+                r"(?x)
+                第(?P<nth>[\d一二三四五](?:・?第?[\d一二三四五])*)(?P<nth_day>[月火水木金土日]+)
+                | 隔週(?P<biweekly>[月火水木金土日]+)
+                | (?P<after>祝の翌日|祝翌日)
+                | (?P<day>[月火水木金土日祝]+)(?P<next>\(祝の場合は翌日\))?
+                | (?:(?P<year>\d{4})年\s*)?
+                  (?P<from_m>\d+)/(?P<from_d>\d+)(?:[〜～~\-](?P<until_m>\d+)/(?P<until_d>\d+))?
+                | (?P<ny>年末年始)
+                | (?P<none>無休)
+                | (?P<unst>不定休)",
+            )
+            .ok_or_else(|| {
+                sc.mismatch("unexpected text")
+                    .into_error(code, Field::RegularHoliday, line)
+            })?;
+
+        if let Some(nth) = caps.name("nth") {
+            let nth = nth
+                .as_str()
+                .chars()
+                .filter_map(nth_number)
+                .collect::<Vec<_>>();
+            for c in caps["nth_day"].chars() {
+                ret.push(HolidayRule::NthWeekday {
+                    weekday: c.to_string(),
+                    nth: nth.clone(),
+                });
+            }
+        } else if let Some(days) = caps.name("biweekly") {
+            for c in days.as_str().chars() {
+                ret.push(HolidayRule::Biweekly {
+                    weekday: c.to_string(),
+                });
+            }
+        } else if caps.name("after").is_some() {
+            ret.push(HolidayRule::DayAfterHoliday);
+        } else if let Some(days) = caps.name("day") {
+            for c in days.as_str().chars() {
+                ret.push(if c == '祝' {
+                    HolidayRule::Holiday
+                } else {
+                    HolidayRule::Weekday {
+                        weekday: c.to_string(),
+                        next_if_holiday: caps.name("next").is_some(),
+                    }
+                });
+            }
+        } else if caps.name("from_m").is_some() {
+            let md = |m: &str, d: &str| -> Result<MonthDay, ParseError> {
+                format!("{}/{}", &caps[m], &caps[d])
+                    .parse()
+                    .map_err(|e: String| {
+                        sc.mismatch(&e)
+                            .into_error(code, Field::RegularHoliday, line)
+                    })
+            };
+            let from = md("from_m", "from_d")?;
+            let until = if caps.name("until_m").is_some() {
+                md("until_m", "until_d")?
+            } else {
+                from
+            };
+
+            match caps.name("year") {
+                // 年が書いてあれば、その年だけの休み
+                Some(year) => {
+                    let year: i32 = year.as_str().parse().unwrap();
+                    let date = |md: MonthDay, year: i32| {
+                        NaiveDate::from_ymd_opt(year, md.month, md.day).ok_or_else(|| {
+                            sc.mismatch("invalid date").into_error(
+                                code,
+                                Field::RegularHoliday,
+                                line,
+                            )
+                        })
+                    };
+                    let from_date = date(from, year)?;
+                    // 「12/30〜1/3」は翌年まで
+                    let until_date = date(until, if until < from { year + 1 } else { year })?;
+                    ret.push(HolidayRule::Period {
+                        from: from_date,
+                        until: until_date,
+                    });
+                }
+                None => ret.push(HolidayRule::Dates { from, until }),
+            }
+        } else if caps.name("ny").is_some() {
+            ret.push(HolidayRule::YearEnd);
+        } else if caps.name("none").is_some() {
+            none = true;
+        } else if caps.name("unst").is_some() {
+            irregular = true;
+        }
+    }
+
+    // 無休なのに定休日があるのはおかしい
+    if none && !ret.is_empty() {
+        return Err(sc.mismatch("無休 with other holidays").into_error(
+            code,
+            Field::RegularHoliday,
+            line,
+        ));
+    }
+
+    Ok((ret, irregular))
+}
+
+/// 住所を郵便番号、都道府県、区市町村、町名、番地、ビル名、階に分ける
+pub fn parse_address(
+    code: usize,
+    s: &str,
+    norm: &Normalizer,
+    fired: &mut Vec<String>,
+) -> Result<Address, ParseError> {
+    let line = s;
+
+    // 正規化。改行も空白にする
+    let s = s
+        .replace("<br>", " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let s = norm.apply(Field::Address, &s, fired);
+
+    let mut sc = Scanner::new(&s);
+    let err =
+        |sc: &Scanner, message: &str| sc.mismatch(message).into_error(code, Field::Address, line);
+
+    let postal_code = sc
+        .eat(r"〒? ?(\d{3})-?(\d{4}) ?")
+        .map(|caps| format!("{}-{}", &caps[1], &caps[2]));
+    let prefecture = sc
+        .eat(r"(東京都|北海道|(?:大阪|京都)府|\S{2,3}県) ?")
+        .map(|caps| caps[1].to_string());
+    let municipality = sc
+        .eat(r"(\S+?[区市町村]) ?")
+        .ok_or_else(|| err(&sc, "municipality expected"))?[1]
+        .to_string();
+    let town = sc
+        .eat(r"([^\d ]+) ?")
+        .ok_or_else(|| err(&sc, "town expected"))?[1]
+        .to_string();
+    let block = sc
+        .eat(r"\d+(?:-\d+)*")
+        .ok_or_else(|| err(&sc, "block number expected"))?[0]
+        .to_string();
+
+    let rest = s[sc.pos..].trim();
+
+    // 「1階・2階」「B1F」「中2階」のように末尾に書かれた階
+    let floor_re =
+        Regex::new(r"((?:(?:B|中)?\d+(?:F|階)?[・,])*(?:B|中)?\d+(?:F|階)|B\d+)$").unwrap();
+    let (building, floor) = match floor_re.find(rest) {
+        Some(m) => (&rest[..m.start()], Some(m.as_str().to_string())),
+        None => (rest, None),
+    };
+    let building = Some(building.trim())
+        .filter(|b| !b.is_empty())
+        .map(|b| b.to_string());
+
+    Ok(Address {
+        postal_code,
+        prefecture,
+        municipality,
+        town,
+        block,
+        building,
+        floor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{write_pretty_json, RawInfo};
+    use serde_json::{json, Value};
+    use std::{
+        env,
+        fs::{self, File},
+        path::PathBuf,
+    };
+
+    fn path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(name)
+    }
+
+    fn normalizer() -> Normalizer {
+        Normalizer::load(path("normalize.json")).unwrap()
+    }
+
+    fn parse(s: &str) -> Vec<BussinessHours> {
+        parse_business_hours(0, s, &normalizer(), &mut vec![]).unwrap()
+    }
+
+    /// 「区分 曜日 開店-閉店 LO」と、あれば期間と注記
+    fn summary(bhs: &[BussinessHours]) -> Vec<String> {
+        bhs.iter()
+            .map(|bh| {
+                let mut s = format!(
+                    "{} {} {}-{} {}",
+                    bh.meal.as_deref().unwrap_or("-"),
+                    bh.day_of_week.as_deref().unwrap_or("-"),
+                    bh.open,
+                    bh.close,
+                    bh.lo.as_ref().map_or("-".to_string(), |lo| lo.to_string())
+                );
+                if bh.from.is_some() || bh.until.is_some() {
+                    let md = |d: &Option<MonthDay>| d.map_or(String::new(), |d| d.to_string());
+                    s += &format!(" [{}〜{}]", md(&bh.from), md(&bh.until));
+                }
+                if let Some(note) = &bh.note {
+                    s += &format!(" {:?}", note);
+                }
+                s
+            })
+            .collect()
+    }
+
+    #[test]
+    fn several_meals_in_a_line() {
+        assert_eq!(
+            summary(&parse(
+                "ランチ（月～日・祝） 11:00～15:00　ディナー（平日）\t17:00～22:00（LO21:30）"
+            )),
+            [
+                "ランチ 月 11:00-15:00 -",
+                "ランチ 火 11:00-15:00 -",
+                "ランチ 水 11:00-15:00 -",
+                "ランチ 木 11:00-15:00 -",
+                "ランチ 金 11:00-15:00 -",
+                "ランチ 土 11:00-15:00 -",
+                "ランチ 日 11:00-15:00 -",
+                "ランチ 祝 11:00-15:00 -",
+                "ディナー 月 17:00-22:00 21:30",
+                "ディナー 火 17:00-22:00 21:30",
+                "ディナー 水 17:00-22:00 21:30",
+                "ディナー 木 17:00-22:00 21:30",
+                "ディナー 金 17:00-22:00 21:30",
+            ]
+        );
+    }
+
+    #[test]
+    fn days_before_meal() {
+        assert_eq!(
+            summary(&parse("土ディナー：18:00〜21:00")),
+            ["ディナー 土 18:00-21:00 -"]
+        );
+    }
+
+    #[test]
+    fn several_ranges() {
+        assert_eq!(
+            summary(&parse("11:30～14:00、17:30～翌2:00")),
+            ["- - 11:30-14:00 -", "- - 17:30-26:00 -"]
+        );
+    }
+
+    #[test]
+    fn lo_without_minute() {
+        assert_eq!(
+            summary(&parse("ディナー 17:00～23:00（LO 22）")),
+            ["ディナー - 17:00-23:00 22:00"]
+        );
+        assert_eq!(
+            summary(&parse("17:00～23:00（LO）")),
+            ["- - 17:00-23:00 23:00"]
+        );
+    }
+
+    #[test]
+    fn notes() {
+        let bhs = parse("ディナー（立ち飲み） 17:00～21:00（LO） ※混雑時は延長");
+        assert_eq!(
+            summary(&bhs),
+            [r#"ディナー - 17:00-21:00 21:00 "立ち飲み ※混雑時は延長""#]
+        );
+    }
+
+    #[test]
+    fn month_only_meal() {
+        assert_eq!(
+            summary(&parse(
+                "9月はランチのみの営業です\nランチ 11:30～14:30(LO14:00)\nカフェ 15:00～17:00"
+            )),
+            vec![
+                "ランチ - 11:30-14:30 14:00",
+                "カフェ - 15:00-17:00 - [10/1〜8/31]"
+            ]
+        );
+        assert_eq!(
+            summary(&parse(
+                "1月はディナーのみ\nランチ 11:30～14:30\nディナー 17:00～22:00"
+            )),
+            vec![
+                "ランチ - 11:30-14:30 - [2/1〜12/31]",
+                "ディナー - 17:00-22:00 -"
+            ]
+        );
+        // 全角の数字でも落ちない
+        assert_eq!(
+            summary(&parse("９月はランチのみ\nランチ 11:30～14:30")).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn dated_holiday() {
+        let (rules, _) =
+            parse_regular_holiday(0, "2020年 8/13～8/16、年末年始", &normalizer(), &mut vec![])
+                .unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                HolidayRule::Period {
+                    from: NaiveDate::from_ymd_opt(2020, 8, 13).unwrap(),
+                    until: NaiveDate::from_ymd_opt(2020, 8, 16).unwrap(),
+                },
+                HolidayRule::YearEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn error_span() {
+        let errs = parse_business_hours(1, "ディナー 18:00～深夜", &normalizer(), &mut vec![])
+            .unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].normalized, "ディナー 18:00〜深夜");
+        assert_eq!((errs[0].pos, errs[0].len), (11, 2));
+        assert_eq!(errs[0].message, "expected time");
+    }
+
+    #[test]
+    fn address_parts() {
+        let a = parse_address(
+            0,
+            "〒101‐0061<br>東京都千代田区三崎町2‐11‐13\u{3000}MMビル2階",
+            &normalizer(),
+            &mut vec![],
+        )
+        .unwrap();
+        assert_eq!(a.postal_code.as_deref(), Some("101-0061"));
+        assert_eq!(a.prefecture.as_deref(), Some("東京都"));
+        assert_eq!(
+            (a.municipality.as_str(), a.town.as_str(), a.block.as_str()),
+            ("千代田区", "三崎町", "2-11-13")
+        );
+        assert_eq!(a.building.as_deref(), Some("MMビル"));
+        assert_eq!(a.floor.as_deref(), Some("2階"));
+
+        let a =
+            parse_address(0, "千代田区六番町15番地 1・2階", &normalizer(), &mut vec![]).unwrap();
+        assert_eq!((a.town.as_str(), a.block.as_str()), ("六番町", "15"));
+        assert_eq!(a.building, None);
+        assert_eq!(a.floor.as_deref(), Some("1・2階"));
+
+        let a =
+            parse_address(0, "千代田区神田小川町2丁目 3-1", &normalizer(), &mut vec![]).unwrap();
+        assert_eq!((a.town.as_str(), a.block.as_str()), ("神田小川町", "2-3-1"));
+
+        // 丁目で終わる住所は、後ろにハイフンを残さない
+        let a = parse_address(0, "千代田区神田小川町3丁目", &normalizer(), &mut vec![]).unwrap();
+        assert_eq!((a.town.as_str(), a.block.as_str()), ("神田小川町", "3"));
+        assert_eq!(a.building, None);
+
+        let a = parse_address(
+            0,
+            "千代田区神田神保町1丁目 神保町ビル2F",
+            &normalizer(),
+            &mut vec![],
+        )
+        .unwrap();
+        assert_eq!((a.town.as_str(), a.block.as_str()), ("神田神保町", "1"));
+        assert_eq!(a.building.as_deref(), Some("神保町ビル"));
+        assert_eq!(a.floor.as_deref(), Some("2F"));
+    }
+
+    fn parse_shop(info: &RawInfo, norm: &Normalizer) -> Value {
+        let to_value = |r: Result<Vec<BussinessHours>, Vec<ParseError>>| match r {
+            Ok(bhs) => json!(summary(&bhs)),
+            Err(es) => json!({ "errors": es.iter().map(|e| e.to_string()).collect::<Vec<_>>() }),
+        };
+
+        let (bh, alt) = split_schedules(&info.business_hours);
+        json!({
+            "code": info.code,
+            "business_hours": info.business_hours,
+            "parsed": to_value(parse_business_hours(info.code, &bh, norm, &mut vec![])),
+            "alt_parsed": alt.map(|s| to_value(parse_business_hours(info.code, &s, norm, &mut vec![]))),
+        })
+    }
+
+    /// sanitized.json のすべての営業時間を読んだ結果を tests/golden/business_hours.json と比べる
+    ///
+    /// パーサや sanitized.json を変えたら `UPDATE_GOLDEN=1 cargo test` で作り直して差分を確かめる。
+    #[test]
+    fn golden_business_hours() {
+        let infos: Vec<RawInfo> =
+            serde_json::from_reader(File::open(path("sanitized.json")).unwrap()).unwrap();
+        let norm = normalizer();
+        let actual = infos
+            .iter()
+            .map(|info| parse_shop(info, &norm))
+            .collect::<Vec<_>>();
+
+        let golden = path("tests/golden/business_hours.json");
+
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::create_dir_all(golden.parent().unwrap()).unwrap();
+            write_pretty_json(&golden, &actual).unwrap();
+            return;
+        }
+
+        let expected: Vec<Value> = serde_json::from_reader(File::open(&golden).unwrap()).unwrap();
+
+        assert_eq!(actual.len(), expected.len(), "number of shops");
+        for (a, e) in actual.iter().zip(&expected) {
+            assert_eq!(a, e, "[{}]", a["code"]);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+//! sanitized.json と raw.json の対応づけ
+//!
+//! 店舗コードで対応づける。同じコードの店舗が複数あれば URL で区別する
+//! （日乃屋カレー九段下とガヴィアル コレド室町2店はどちらも 1815）。
+//! コードで見つからないときだけ店名のあいまい一致を使い、それも報告する。
+
+use crate::RawInfo;
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+
+/// あいまい一致とみなす編集距離の上限（正規化した店名の文字数に対する割合）
+const FUZZY_RATIO: f64 = 0.25;
+
+/// 対応づけで気になったこと
+pub enum Note {
+    /// コードは同じだが店名が違う
+    Name {
+        code: usize,
+        sanitized: String,
+        raw: String,
+    },
+    /// コードは同じだが URL が違う
+    Url {
+        code: usize,
+        sanitized: String,
+        raw: String,
+    },
+    /// 同じコードの店舗が複数あり、URL でも区別できなかった
+    Ambiguous { code: usize, url: String },
+    /// コードで見つからず、店名のあいまい一致で対応づけた
+    Fuzzy {
+        code: usize,
+        raw_code: usize,
+        sanitized: String,
+        raw: String,
+        distance: usize,
+    },
+    /// 対応する raw.json の店舗がない
+    Missing { code: usize, name: String },
+    /// sanitized.json のどの店舗にも対応づけられなかった
+    Unused { code: usize, name: String },
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Note::Name {
+                code,
+                sanitized,
+                raw,
+            } => write!(f, "[{}] name differs: {} / {} (raw)", code, sanitized, raw),
+            Note::Url {
+                code,
+                sanitized,
+                raw,
+            } => write!(f, "[{}] url differs: {} / {} (raw)", code, sanitized, raw),
+            Note::Ambiguous { code, url } => {
+                write!(
+                    f,
+                    "[{}] several shops have this code, none with url {}",
+                    code, url
+                )
+            }
+            Note::Fuzzy {
+                code,
+                raw_code,
+                sanitized,
+                raw,
+                distance,
+            } => write!(
+                f,
+                "[{}] matched by name: {} ~ [{}] {} (raw, distance {})",
+                code, sanitized, raw_code, raw, distance
+            ),
+            Note::Missing { code, name } => write!(f, "[{}] not found in raw.json: {}", code, name),
+            Note::Unused { code, name } => {
+                write!(f, "[{}] only in raw.json: {}", code, name)
+            }
+        }
+    }
+}
+
+/// `infos` のそれぞれに対応する `raws` の店舗
+pub fn reconcile<'a>(
+    infos: &[RawInfo],
+    raws: &'a [RawInfo],
+) -> (Vec<Option<&'a RawInfo>>, Vec<Note>) {
+    let mut used = vec![false; raws.len()];
+    let mut ret = vec![];
+    let mut notes = vec![];
+
+    for info in infos {
+        let by_code = raws
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.code == info.code)
+            .collect::<Vec<_>>();
+
+        let found = match by_code.len() {
+            0 => None,
+            1 => Some(by_code[0]),
+            _ => {
+                let found = by_code.iter().copied().find(|(_, r)| r.url == info.url);
+                if found.is_none() {
+                    notes.push(Note::Ambiguous {
+                        code: info.code,
+                        url: info.url.clone(),
+                    });
+                }
+                found
+            }
+        };
+
+        let found = found.or_else(|| {
+            let (i, raw, distance) = fuzzy_find(&info.name, raws, &used)?;
+            notes.push(Note::Fuzzy {
+                code: info.code,
+                raw_code: raw.code,
+                sanitized: info.name.clone(),
+                raw: raw.name.clone(),
+                distance,
+            });
+            Some((i, raw))
+        });
+
+        let (i, raw) = match found {
+            Some(found) => found,
+            None => {
+                notes.push(Note::Missing {
+                    code: info.code,
+                    name: info.name.clone(),
+                });
+                ret.push(None);
+                continue;
+            }
+        };
+
+        if raw.name != info.name {
+            notes.push(Note::Name {
+                code: info.code,
+                sanitized: info.name.clone(),
+                raw: raw.name.clone(),
+            });
+        }
+        if raw.url != info.url {
+            notes.push(Note::Url {
+                code: info.code,
+                sanitized: info.url.clone(),
+                raw: raw.url.clone(),
+            });
+        }
+
+        used[i] = true;
+        ret.push(Some(raw));
+    }
+
+    for (raw, _) in raws.iter().zip(&used).filter(|(_, used)| !**used) {
+        notes.push(Note::Unused {
+            code: raw.code,
+            name: raw.name.clone(),
+        });
+    }
+
+    (ret, notes)
+}
+
+/// 比較用に店名を正規化する
+fn normalize_name(s: &str) -> String {
+    s.replace("&amp;", "&")
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// まだ対応づけられていない店舗から、店名がいちばん近いものを探す
+fn fuzzy_find<'a>(
+    name: &str,
+    raws: &'a [RawInfo],
+    used: &[bool],
+) -> Option<(usize, &'a RawInfo, usize)> {
+    let name = normalize_name(name);
+    let limit = (name.chars().count() as f64 * FUZZY_RATIO).ceil() as usize;
+
+    raws.iter()
+        .enumerate()
+        .filter(|(i, _)| !used[*i])
+        .map(|(i, r)| (i, r, strsim::levenshtein(&name, &normalize_name(&r.name))))
+        .filter(|(_, _, d)| *d <= limit)
+        .min_by_key(|(_, _, d)| *d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(code: usize, name: &str, url: &str) -> RawInfo {
+        RawInfo {
+            code,
+            name: name.to_string(),
+            course: "A".to_string(),
+            url: url.to_string(),
+            tn_url: String::new(),
+            address: String::new(),
+            business_hours: String::new(),
+            regular_holiday: String::new(),
+            table: vec![],
+            menu_image: None,
+        }
+    }
+
+    /// 対応づけた raw.json の店舗の URL と、メモ
+    fn run(infos: &[RawInfo], raws: &[RawInfo]) -> (Vec<Option<String>>, Vec<String>) {
+        let (found, notes) = reconcile(infos, raws);
+        (
+            found.iter().map(|r| r.map(|r| r.url.clone())).collect(),
+            notes.iter().map(|n| n.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn by_code() {
+        let raws = vec![raw(1, "カレー屋", "u1"), raw(2, "咖喱店", "u2")];
+        let infos = vec![raw(2, "咖喱店", "u2"), raw(1, "カレー屋 本店", "u1")];
+        assert_eq!(
+            run(&infos, &raws),
+            (
+                vec![Some("u2".to_string()), Some("u1".to_string())],
+                vec!["[1] name differs: カレー屋 本店 / カレー屋 (raw)".to_string()],
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_code_by_url() {
+        let raws = vec![
+            raw(1815, "日乃屋カレー九段下", "p4882"),
+            raw(1815, "欧風カレー ガヴィアル コレド室町2店", "p8296"),
+        ];
+        let infos = vec![
+            raw(1815, "欧風カレー ガヴィアル コレド室町2店", "p8296"),
+            raw(1815, "日乃屋カレー九段下", "p4882"),
+        ];
+        assert_eq!(
+            run(&infos, &raws),
+            (
+                vec![Some("p8296".to_string()), Some("p4882".to_string())],
+                vec![],
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_code_ambiguous() {
+        let raws = vec![
+            raw(1815, "日乃屋カレー九段下", "p4882"),
+            raw(1815, "欧風カレー ガヴィアル コレド室町2店", "p8296"),
+        ];
+        // URL が変わっていたら店名で探す
+        let infos = vec![raw(1815, "日乃屋カレー 九段下", "p9999")];
+        let (found, notes) = run(&infos, &raws);
+        assert_eq!(found, vec![Some("p4882".to_string())]);
+        assert_eq!(
+            notes[..2],
+            [
+                "[1815] several shops have this code, none with url p9999".to_string(),
+                "[1815] matched by name: 日乃屋カレー 九段下 ~ [1815] 日乃屋カレー九段下 (raw, distance 0)"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_name() {
+        // コードが変わっていても、全角・半角や大文字・小文字、空白、&amp; の違いは同じ店名
+        let raws = vec![raw(1, "Cafe&amp;Bar BASE CAMP", "u1")];
+        let infos = vec![raw(2, "ｃａｆｅ＆ｂａｒ basecamp", "u1")];
+        let (found, notes) = run(&infos, &raws);
+        assert_eq!(found, vec![Some("u1".to_string())]);
+        assert!(notes[0].contains("distance 0"), "{}", notes[0]);
+    }
+
+    #[test]
+    fn fuzzy_threshold() {
+        // 8 文字なら編集距離 2 まで
+        let infos = vec![raw(9, "abcdefgh", "u")];
+
+        let (found, notes) = run(&infos, &[raw(1, "abcdefxy", "u")]);
+        assert_eq!(found, vec![Some("u".to_string())]);
+        assert!(notes[0].contains("distance 2"), "{}", notes[0]);
+
+        let (found, notes) = run(&infos, &[raw(1, "abcdexyz", "u")]);
+        assert_eq!(found, vec![None]);
+        assert_eq!(
+            notes,
+            vec![
+                "[9] not found in raw.json: abcdefgh".to_string(),
+                "[1] only in raw.json: abcdexyz".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_skips_used() {
+        // コードで対応づけた店舗は、あいまい一致の候補にしない
+        let raws = vec![raw(1, "カレー屋", "u1")];
+        let infos = vec![raw(1, "カレー屋", "u1"), raw(2, "カレー屋", "u2")];
+        let (found, notes) = run(&infos, &raws);
+        assert_eq!(found, vec![Some("u1".to_string()), None]);
+        assert_eq!(
+            notes,
+            vec!["[2] not found in raw.json: カレー屋".to_string()]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! raw.json に手作業の修正（overrides.json）を当てて sanitized.json を作る
+//!
+//! 修正は店舗コードごとに、置き換える項目とその値を書く。`original` には
+//! 修正を書いたときの raw.json の値を残しておき、再取得でサイトの記載が
+//! 変わっていたら警告する。
+
+use crate::RawInfo;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Override {
+    pub code: usize,
+    /// 同じコードの店舗が複数あるときに区別する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub field: Field,
+    pub value: String,
+    /// 修正の理由
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// 修正を書いたときの raw.json の値
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Name,
+    Address,
+    BusinessHours,
+    RegularHoliday,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Field::Name => "name",
+            Field::Address => "address",
+            Field::BusinessHours => "business_hours",
+            Field::RegularHoliday => "regular_holiday",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Field {
+    fn get(self, info: &RawInfo) -> &str {
+        match self {
+            Field::Name => &info.name,
+            Field::Address => &info.address,
+            Field::BusinessHours => &info.business_hours,
+            Field::RegularHoliday => &info.regular_holiday,
+        }
+    }
+
+    fn get_mut(self, info: &mut RawInfo) -> &mut String {
+        match self {
+            Field::Name => &mut info.name,
+            Field::Address => &mut info.address,
+            Field::BusinessHours => &mut info.business_hours,
+            Field::RegularHoliday => &mut info.regular_holiday,
+        }
+    }
+}
+
+/// `raws` に `overrides` を当てる。当てられなかったものや、
+/// サイトの記載が変わっていたものは警告として返す
+pub fn apply_overrides(raws: &[RawInfo], overrides: &[Override]) -> (Vec<RawInfo>, Vec<String>) {
+    let mut ret = raws.to_vec();
+    let mut warnings = vec![];
+
+    for ov in overrides {
+        let targets = raws
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.code == ov.code && ov.url.as_ref().is_none_or(|u| u == &r.url))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        let i = match targets[..] {
+            [i] => i,
+            [] => {
+                warnings.push(format!("[{}] {}: no such shop", ov.code, ov.field));
+                continue;
+            }
+            _ => {
+                warnings.push(format!(
+                    "[{}] {}: {} shops have this code; specify url",
+                    ov.code,
+                    ov.field,
+                    targets.len()
+                ));
+                continue;
+            }
+        };
+
+        let current = ov.field.get(&raws[i]);
+
+        if let Some(original) = &ov.original {
+            if original != current {
+                warnings.push(format!(
+                    "[{}] {}: upstream text has changed\n    was: {}\n    now: {}",
+                    ov.code, ov.field, original, current
+                ));
+            }
+        }
+        if ov.value == current {
+            warnings.push(format!(
+                "[{}] {}: override is the same as upstream",
+                ov.code, ov.field
+            ));
+        }
+
+        *ov.field.get_mut(&mut ret[i]) = ov.value.clone();
+    }
+
+    (ret, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(code: usize, url: &str, regular_holiday: &str) -> RawInfo {
+        RawInfo {
+            code,
+            name: format!("店舗{}", code),
+            course: "A".to_string(),
+            url: url.to_string(),
+            tn_url: String::new(),
+            address: String::new(),
+            business_hours: String::new(),
+            regular_holiday: regular_holiday.to_string(),
+            table: vec![],
+            menu_image: None,
+        }
+    }
+
+    fn holiday(code: usize, value: &str, original: Option<&str>) -> Override {
+        Override {
+            code,
+            url: None,
+            field: Field::RegularHoliday,
+            value: value.to_string(),
+            reason: None,
+            original: original.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn applied() {
+        let raws = vec![raw(1, "u1", "日曜日（祝日は営業）"), raw(2, "u2", "月曜日")];
+        let overrides = vec![holiday(1, "日曜日", Some("日曜日（祝日は営業）"))];
+        let (sanitized, warnings) = apply_overrides(&raws, &overrides);
+        assert_eq!(sanitized[0].regular_holiday, "日曜日");
+        assert_eq!(sanitized[1].regular_holiday, "月曜日");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn upstream_changed() {
+        let raws = vec![raw(1, "u1", "日曜日・祝日")];
+        let overrides = vec![holiday(1, "日曜日", Some("日曜日（祝日は営業）"))];
+        let (sanitized, warnings) = apply_overrides(&raws, &overrides);
+        // 警告するが、修正は当てる
+        assert_eq!(sanitized[0].regular_holiday, "日曜日");
+        assert_eq!(
+            warnings,
+            vec!["[1] regular_holiday: upstream text has changed\n    was: 日曜日（祝日は営業）\n    now: 日曜日・祝日"]
+        );
+    }
+
+    #[test]
+    fn same_as_upstream() {
+        let raws = vec![raw(1, "u1", "日曜日")];
+        let (_, warnings) = apply_overrides(&raws, &[holiday(1, "日曜日", None)]);
+        assert_eq!(
+            warnings,
+            vec!["[1] regular_holiday: override is the same as upstream"]
+        );
+    }
+
+    #[test]
+    fn unknown_code() {
+        let raws = vec![raw(1, "u1", "日曜日")];
+        let (sanitized, warnings) = apply_overrides(&raws, &[holiday(9, "月曜日", None)]);
+        assert_eq!(sanitized[0].regular_holiday, "日曜日");
+        assert_eq!(warnings, vec!["[9] regular_holiday: no such shop"]);
+    }
+
+    #[test]
+    fn duplicate_code() {
+        let raws = vec![raw(1815, "u1", "日曜日"), raw(1815, "u2", "日曜日")];
+
+        let (sanitized, warnings) = apply_overrides(&raws, &[holiday(1815, "月曜日", None)]);
+        assert_eq!(sanitized[0].regular_holiday, "日曜日");
+        assert_eq!(sanitized[1].regular_holiday, "日曜日");
+        assert_eq!(
+            warnings,
+            vec!["[1815] regular_holiday: 2 shops have this code; specify url"]
+        );
+
+        let ov = Override {
+            url: Some("u2".to_string()),
+            ..holiday(1815, "月曜日", None)
+        };
+        let (sanitized, warnings) = apply_overrides(&raws, &[ov]);
+        assert_eq!(sanitized[0].regular_holiday, "日曜日");
+        assert_eq!(sanitized[1].regular_holiday, "月曜日");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        // URL が違えば、コードが合っていても当てない
+        let ov = Override {
+            url: Some("u3".to_string()),
+            ..holiday(1815, "月曜日", None)
+        };
+        let (_, warnings) = apply_overrides(&raws, &[ov]);
+        assert_eq!(warnings, vec!["[1815] regular_holiday: no such shop"]);
+    }
+}
@@ -0,0 +1,53 @@
+//! 店の住所
+
+use serde::{Deserialize, Serialize};
+
+/// 住所を区切ったもの。crawler が `address` から作る
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    /// "101-0052"
+    pub postal_code: Option<String>,
+    /// 書かれていなければ None
+    pub prefecture: Option<String>,
+    /// 区市町村。"千代田区"
+    pub municipality: String,
+    /// 町名。"神田小川町"
+    pub town: String,
+    /// 丁目・番地・号をハイフンでつないだもの。"3-9"
+    pub block: String,
+    /// ビル名など、番地より後ろの部分から階を除いたもの
+    pub building: Option<String>,
+    /// "2階" "B1F" など。書かれたままの表記
+    pub floor: Option<String>,
+}
+
+impl Address {
+    /// 近所の店をまとめるための町名
+    ///
+    /// 「神田神保町」と「神保町」のように「神田」を付けたり付けなかったりするので、
+    /// 先頭の「神田」は除く。「内神田」「外神田」はそのまま。
+    pub fn neighbourhood(&self) -> &str {
+        match self.town.strip_prefix("神田") {
+            Some(rest) if !rest.is_empty() => rest,
+            _ => &self.town,
+        }
+    }
+
+    /// 地図を検索するための文字列。ビル名と階は付けない
+    pub fn map_query(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            self.prefecture.as_deref().unwrap_or(""),
+            self.municipality,
+            self.town,
+            self.block
+        )
+    }
+}
+
+/// 緯度経度（世界測地系）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub lat: f64,
+    pub lng: f64,
+}
@@ -0,0 +1,39 @@
+//! スタンプラリーの開催内容（`event.json`）
+
+use crate::YearEnd;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct EventConfig {
+    pub title: String,
+    /// 店舗一覧のページ
+    pub index_url: String,
+    pub courses: Vec<Course>,
+    /// 参加店舗数
+    pub shop_count: usize,
+    /// フリーコースの制覇に必要な店舗数
+    pub free_course_size: usize,
+    /// 制覇したコース数ごとの称号。先頭は 0 コース
+    pub title_tiers: Vec<String>,
+    #[serde(default)]
+    pub year_end: YearEnd,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Course {
+    pub name: String,
+    /// カードのヘッダの色
+    pub color: String,
+}
+
+impl EventConfig {
+    pub fn course_index(&self, name: &str) -> Option<usize> {
+        self.courses.iter().position(|c| c.name == name)
+    }
+
+    /// 制覇したコース数に対する称号
+    pub fn title_tier(&self, cleared: usize) -> &str {
+        let i = cleared.min(self.title_tiers.len().saturating_sub(1));
+        self.title_tiers.get(i).map_or("", |s| s.as_str())
+    }
+}
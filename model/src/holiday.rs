@@ -0,0 +1,394 @@
+//! 日本の祝日
+//!
+//! 祝日法の規定から計算する。内閣府の `syukujitsu.csv` などで
+//! 上書きしたい年があれば `set_calendar` で登録する。
+//! https://www8.cao.go.jp/chosei/shukujitsu/gaiyou.html
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::RwLock,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+}
+
+/// 計算結果を上書きする祝日の一覧
+///
+/// ここに含まれる年は、計算せずにこの一覧だけを使う。
+#[derive(Debug, Default, Clone)]
+pub struct HolidayCalendar {
+    holidays: BTreeMap<NaiveDate, String>,
+    years: BTreeSet<i32>,
+}
+
+impl HolidayCalendar {
+    pub const fn new() -> Self {
+        Self {
+            holidays: BTreeMap::new(),
+            years: BTreeSet::new(),
+        }
+    }
+
+    pub fn from_holidays(holidays: impl IntoIterator<Item = Holiday>) -> Self {
+        let mut ret = Self::new();
+        for h in holidays {
+            ret.years.insert(h.date.year());
+            ret.holidays.insert(h.date, h.name);
+        }
+        ret
+    }
+
+    /// 内閣府の `syukujitsu.csv`（UTF-8 に変換したもの）を読む
+    ///
+    /// ```text
+    /// 国民の祝日・休日月日,国民の祝日・休日名称
+    /// 1955/1/1,元日
+    /// ```
+    pub fn from_csv(s: &str) -> Result<Self, String> {
+        let mut holidays = vec![];
+
+        for (i, line) in s.lines().enumerate().skip(1) {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let err = || format!("line {}: invalid row: {}", i + 1, line);
+
+            let (date, name) = line.split_once(',').ok_or_else(err)?;
+            let ymd = date
+                .split('/')
+                .map(|n| n.parse::<u32>().map_err(|_| err()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if ymd.len() != 3 {
+                return Err(err());
+            }
+            let date = NaiveDate::from_ymd_opt(ymd[0] as i32, ymd[1], ymd[2]).ok_or_else(err)?;
+
+            holidays.push(Holiday {
+                date,
+                name: name.trim().to_string(),
+            });
+        }
+
+        Ok(Self::from_holidays(holidays))
+    }
+
+    pub fn holiday_name(&self, date: &NaiveDate) -> Option<String> {
+        if self.years.contains(&date.year()) {
+            self.holidays.get(date).cloned()
+        } else {
+            compute_holiday_name(date).map(|s| s.to_string())
+        }
+    }
+
+    /// `year` の祝日
+    pub fn holidays(&self, year: i32) -> Vec<Holiday> {
+        if self.years.contains(&year) {
+            self.holidays
+                .range(
+                    NaiveDate::from_ymd_opt(year, 1, 1).unwrap()
+                        ..=NaiveDate::from_ymd_opt(year, 12, 31).unwrap(),
+                )
+                .map(|(date, name)| Holiday {
+                    date: *date,
+                    name: name.clone(),
+                })
+                .collect()
+        } else {
+            compute_holidays(year)
+                .into_iter()
+                .map(|(date, name)| Holiday {
+                    date,
+                    name: name.to_string(),
+                })
+                .collect()
+        }
+    }
+}
+
+static CALENDAR: RwLock<HolidayCalendar> = RwLock::new(HolidayCalendar::new());
+
+/// `is_holiday` などが使う上書きを登録する
+pub fn set_calendar(cal: HolidayCalendar) {
+    *CALENDAR.write().unwrap() = cal;
+}
+
+pub fn holiday_name(date: &NaiveDate) -> Option<String> {
+    CALENDAR.read().unwrap().holiday_name(date)
+}
+
+pub fn is_holiday(date: &NaiveDate) -> bool {
+    holiday_name(date).is_some()
+}
+
+pub fn holidays(year: i32) -> Vec<Holiday> {
+    CALENDAR.read().unwrap().holidays(year)
+}
+
+fn compute_holiday_name(date: &NaiveDate) -> Option<&'static str> {
+    compute_holidays(date.year())
+        .into_iter()
+        .find(|(d, _)| d == date)
+        .map(|(_, name)| name)
+}
+
+/// `year` の祝日と休日を計算する。計算できない年は空
+pub fn compute_holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    if !(1949..=2099).contains(&year) {
+        return vec![];
+    }
+
+    let mut ret = national_holidays(year);
+    ret.sort();
+
+    // 国民の休日：前日と翌日が祝日である日
+    if year >= 1988 {
+        let mut sandwiched = vec![];
+        for w in ret.windows(2) {
+            let d = w[0].0.succ_opt().unwrap();
+            if d.succ_opt().unwrap() == w[1].0 && (year >= 2007 || d.weekday() != Weekday::Sun) {
+                sandwiched.push((d, "休日"));
+            }
+        }
+        ret.append(&mut sandwiched);
+        ret.sort();
+    }
+
+    // 振替休日：日曜日の祝日の後の、最初の祝日でない日
+    let mut substitutes = vec![];
+    for (d, _) in ret.iter().filter(|(d, _)| d.weekday() == Weekday::Sun) {
+        if *d < NaiveDate::from_ymd_opt(1973, 4, 12).unwrap() {
+            continue;
+        }
+        let mut s = d.succ_opt().unwrap();
+        if year >= 2007 {
+            while ret.iter().any(|(h, _)| *h == s) {
+                s = s.succ_opt().unwrap();
+            }
+        } else if ret.iter().any(|(h, _)| *h == s) {
+            continue;
+        }
+        substitutes.push((s, "休日"));
+    }
+    ret.append(&mut substitutes);
+    ret.sort();
+
+    ret.into_iter().filter(|(d, _)| d.year() == year).collect()
+}
+
+/// 「国民の祝日」
+fn national_holidays(y: i32) -> Vec<(NaiveDate, &'static str)> {
+    let ymd = |m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let monday = |m, n| NaiveDate::from_weekday_of_month_opt(y, m, Weekday::Mon, n).unwrap();
+
+    let mut ret = vec![(ymd(1, 1), "元日")];
+
+    ret.push(if y >= 2000 {
+        (monday(1, 2), "成人の日")
+    } else {
+        (ymd(1, 15), "成人の日")
+    });
+
+    if y >= 1967 {
+        ret.push((ymd(2, 11), "建国記念の日"));
+    }
+
+    match y {
+        1949..=1988 => ret.push((ymd(4, 29), "天皇誕生日")),
+        1989..=2018 => ret.push((ymd(12, 23), "天皇誕生日")),
+        2019 => {}
+        _ => ret.push((ymd(2, 23), "天皇誕生日")),
+    }
+
+    ret.push((ymd(3, vernal_equinox_day(y)), "春分の日"));
+
+    match y {
+        1989..=2006 => ret.push((ymd(4, 29), "みどりの日")),
+        2007.. => {
+            ret.push((ymd(4, 29), "昭和の日"));
+            ret.push((ymd(5, 4), "みどりの日"));
+        }
+        _ => {}
+    }
+
+    ret.push((ymd(5, 3), "憲法記念日"));
+    ret.push((ymd(5, 5), "こどもの日"));
+
+    match y {
+        1996..=2002 => ret.push((ymd(7, 20), "海の日")),
+        2020 => ret.push((ymd(7, 23), "海の日")),
+        2021 => ret.push((ymd(7, 22), "海の日")),
+        2003.. => ret.push((monday(7, 3), "海の日")),
+        _ => {}
+    }
+
+    match y {
+        2020 => ret.push((ymd(8, 10), "山の日")),
+        2021 => ret.push((ymd(8, 8), "山の日")),
+        2016.. => ret.push((ymd(8, 11), "山の日")),
+        _ => {}
+    }
+
+    match y {
+        1966..=2002 => ret.push((ymd(9, 15), "敬老の日")),
+        2003.. => ret.push((monday(9, 3), "敬老の日")),
+        _ => {}
+    }
+
+    ret.push((ymd(9, autumnal_equinox_day(y)), "秋分の日"));
+
+    match y {
+        1966..=1999 => ret.push((ymd(10, 10), "体育の日")),
+        2000..=2019 => ret.push((monday(10, 2), "体育の日")),
+        2020 => ret.push((ymd(7, 24), "スポーツの日")),
+        2021 => ret.push((ymd(7, 23), "スポーツの日")),
+        2022.. => ret.push((monday(10, 2), "スポーツの日")),
+        _ => {}
+    }
+
+    ret.push((ymd(11, 3), "文化の日"));
+    ret.push((ymd(11, 23), "勤労感謝の日"));
+
+    // 皇室行事などによる一回限りの祝日
+    match y {
+        1959 => ret.push((ymd(4, 10), "結婚の儀")),
+        1989 => ret.push((ymd(2, 24), "大喪の礼")),
+        1990 => ret.push((ymd(11, 12), "即位礼正殿の儀")),
+        1993 => ret.push((ymd(6, 9), "結婚の儀")),
+        2019 => {
+            ret.push((ymd(5, 1), "天皇の即位の日"));
+            ret.push((ymd(10, 22), "即位礼正殿の儀"));
+        }
+        _ => {}
+    }
+
+    ret
+}
+
+// 春分日・秋分日の近似式（1900〜2099 年）
+
+fn vernal_equinox_day(y: i32) -> u32 {
+    let base = if y < 1980 { 20.8357 } else { 20.8431 };
+    equinox_day(base, y)
+}
+
+fn autumnal_equinox_day(y: i32) -> u32 {
+    let base = if y < 1980 { 23.2588 } else { 23.2488 };
+    equinox_day(base, y)
+}
+
+fn equinox_day(base: f64, y: i32) -> u32 {
+    let leap = if y < 1980 {
+        (y - 1983) / 4
+    } else {
+        (y - 1980) / 4
+    };
+    (base + 0.242194 * (y - 1980) as f64 - leap as f64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// `year` の祝日と休日の「月/日」
+    fn dates(year: i32) -> Vec<String> {
+        compute_holidays(year)
+            .into_iter()
+            .map(|(d, _)| format!("{}/{}", d.month(), d.day()))
+            .collect()
+    }
+
+    // 内閣府の「国民の祝日」の一覧と比べる
+
+    #[test]
+    fn year_2019() {
+        assert_eq!(
+            dates(2019),
+            vec![
+                "1/1", "1/14", "2/11", "3/21", "4/29", "4/30", "5/1", "5/2", "5/3", "5/4", "5/5",
+                "5/6", "7/15", "8/11", "8/12", "9/16", "9/23", "10/14", "10/22", "11/3", "11/4",
+                "11/23",
+            ]
+        );
+    }
+
+    #[test]
+    fn year_2020() {
+        assert_eq!(
+            dates(2020),
+            vec![
+                "1/1", "1/13", "2/11", "2/23", "2/24", "3/20", "4/29", "5/3", "5/4", "5/5", "5/6",
+                "7/23", "7/24", "8/10", "9/21", "9/22", "11/3", "11/23",
+            ]
+        );
+    }
+
+    #[test]
+    fn year_2021() {
+        assert_eq!(
+            dates(2021),
+            vec![
+                "1/1", "1/11", "2/11", "2/23", "3/20", "4/29", "5/3", "5/4", "5/5", "7/22", "7/23",
+                "8/8", "8/9", "9/20", "9/23", "11/3", "11/23",
+            ]
+        );
+    }
+
+    #[test]
+    fn names() {
+        let name = |d: NaiveDate| compute_holiday_name(&d);
+
+        // 振替休日
+        assert_eq!(name(ymd(2019, 5, 6)), Some("休日"));
+        // 国民の休日
+        assert_eq!(name(ymd(2019, 4, 30)), Some("休日"));
+        assert_eq!(name(ymd(2015, 9, 22)), Some("休日"));
+        // 東京オリンピックで移動した祝日
+        assert_eq!(name(ymd(2020, 7, 23)), Some("海の日"));
+        assert_eq!(name(ymd(2020, 7, 24)), Some("スポーツの日"));
+        assert_eq!(name(ymd(2021, 7, 22)), Some("海の日"));
+        assert_eq!(name(ymd(2021, 7, 23)), Some("スポーツの日"));
+        assert_eq!(name(ymd(2020, 10, 12)), None);
+        // 2019 年は天皇誕生日がない
+        assert_eq!(name(ymd(2019, 12, 23)), None);
+        assert_eq!(name(ymd(2019, 2, 23)), None);
+    }
+
+    #[test]
+    fn equinox() {
+        assert_eq!(vernal_equinox_day(2020), 20);
+        assert_eq!(vernal_equinox_day(2023), 21);
+        assert_eq!(autumnal_equinox_day(2020), 22);
+        assert_eq!(autumnal_equinox_day(2021), 23);
+        assert_eq!(vernal_equinox_day(1970), 21);
+        assert_eq!(autumnal_equinox_day(1979), 24);
+    }
+
+    #[test]
+    fn calendar_overrides_year() {
+        let cal = HolidayCalendar::from_csv(
+            "国民の祝日・休日月日,国民の祝日・休日名称\n2020/1/1,元日\n2020/3/19,テスト\n",
+        )
+        .unwrap();
+        assert_eq!(
+            cal.holiday_name(&ymd(2020, 3, 19)).as_deref(),
+            Some("テスト")
+        );
+        assert_eq!(cal.holiday_name(&ymd(2020, 3, 20)), None);
+        // 登録していない年は計算する
+        assert_eq!(
+            cal.holiday_name(&ymd(2021, 3, 20)).as_deref(),
+            Some("春分の日")
+        );
+    }
+}
@@ -0,0 +1,706 @@
+//! `info.json` のデータモデル
+//!
+//! crawler と web の両方から使われるので、ここを変更したら
+//! crawler で `info.json` を作り直すこと。
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+pub mod address;
+pub mod event;
+pub mod holiday;
+pub mod regular_holiday;
+
+pub use address::{Address, Location};
+pub use holiday::is_holiday;
+pub use regular_holiday::HolidayRule;
+
+/// 営業時間の区分
+pub const MEALS: &[&str] = &[
+    "モーニング",
+    "ランチ",
+    "カフェ",
+    "ティー",
+    "ディナー",
+    "バー",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RestaurantInfo {
+    pub code: usize,
+    pub name: String,
+    pub course: String,
+    pub url: String,
+    pub tn_url: String,
+    pub address: String,
+    /// `address` を区切ったもの
+    #[serde(default)]
+    pub address_parts: Option<Address>,
+    pub business_hours: Vec<BussinessHours>,
+    /// `business_hours` が一時的な営業時間ならその説明
+    pub business_hours_label: Option<String>,
+    /// ガイドブック記載の営業時間など、併記されているもう一方の営業時間
+    pub alt_business_hours: Option<AltBusinessHours>,
+    pub business_hours_raw: String,
+    pub regular_holiday: Vec<HolidayRule>,
+    /// 不定休あり
+    pub irregular_holiday: bool,
+    pub regular_holiday_raw: String,
+    /// 営業時間に添えられた注意書き
+    #[serde(default)]
+    pub caveats: Vec<Caveat>,
+    /// パース前の正規化で当てはまったルールの名前（crawler の normalize.json）
+    #[serde(default)]
+    pub normalize_rules: Vec<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    /// 全部の席数
+    #[serde(default)]
+    pub seats: Option<u32>,
+    #[serde(default)]
+    pub smoking: Option<Smoking>,
+    /// 公式サイトや SNS
+    #[serde(default)]
+    pub links: Vec<Link>,
+    /// グランプリに出しているカレー
+    #[serde(default)]
+    pub menu: Option<Menu>,
+    /// crawler の geocode で求めた店の位置
+    #[serde(default)]
+    pub location: Option<Location>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AltBusinessHours {
+    pub label: String,
+    pub business_hours: Vec<BussinessHours>,
+}
+
+/// 「※スープがなくなり次第終了」のような注意書きと、その分類
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Caveat {
+    pub text: String,
+    pub flags: Vec<CaveatFlag>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaveatFlag {
+    /// なくなり次第終了
+    SellsOutEarly,
+    /// 貸し切りで休むことがある
+    MayBePrivate,
+    /// 予約が要る
+    ReservationNeeded,
+    /// お客がいれば閉店時刻より遅くまで営業する
+    ExtendedIfBusy,
+    /// お客がいなければ早く閉める
+    MayCloseEarly,
+}
+
+impl CaveatFlag {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CaveatFlag::SellsOutEarly => "売り切れ次第終了",
+            CaveatFlag::MayBePrivate => "貸し切りあり",
+            CaveatFlag::ReservationNeeded => "要予約",
+            CaveatFlag::ExtendedIfBusy => "延長営業あり",
+            CaveatFlag::MayCloseEarly => "早じまいあり",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Smoking {
+    /// 全席禁煙
+    NoSmoking,
+    /// 分煙。喫煙席や喫煙所がある
+    Separated,
+    /// 全席喫煙可
+    Allowed,
+}
+
+impl Smoking {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Smoking::NoSmoking => "禁煙",
+            Smoking::Separated => "分煙",
+            Smoking::Allowed => "喫煙可",
+        }
+    }
+}
+
+/// 店のページの表にあった外部サイトへのリンク
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Link {
+    /// 表の見出し。「公式サイト」など
+    pub label: String,
+    pub url: String,
+}
+
+/// グランプリに出しているカレー
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Menu {
+    pub name: String,
+    /// 円。書かれていなければ None
+    pub price: Option<u32>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+}
+
+/// 検索条件
+#[derive(Debug, Default, Clone)]
+pub struct SearchOptions {
+    /// `MEALS` のどれか
+    pub meal: Option<String>,
+    /// `alt_business_hours` があればそちらで検索する
+    pub use_alt: bool,
+    /// 定休日が「年末年始」の店が休む期間
+    pub year_end: YearEnd,
+    /// いつまで入れる店を営業中とみなすか
+    pub cutoff: Cutoff,
+}
+
+/// 営業中とみなす締め切り
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Cutoff {
+    /// LO の指定した分数前まで。店まで歩く時間の分だけ早める
+    BeforeLastOrder(usize),
+    /// LO まで。LO のない店は閉店まで
+    #[default]
+    LastOrder,
+    /// 閉店まで
+    Close,
+}
+
+/// 営業中の営業時間の残り時間（分）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Remaining {
+    /// LO までの分数。LO がなければ None、過ぎていれば 0
+    pub to_lo: Option<usize>,
+    pub to_close: usize,
+}
+
+impl Remaining {
+    /// `cutoff` までの分数。過ぎていれば 0
+    pub fn until(&self, cutoff: Cutoff) -> usize {
+        let to_lo = self.to_lo.unwrap_or(self.to_close);
+        match cutoff {
+            Cutoff::BeforeLastOrder(buffer) => to_lo.saturating_sub(buffer),
+            Cutoff::LastOrder => to_lo,
+            Cutoff::Close => self.to_close,
+        }
+    }
+}
+
+/// 年末年始の休業期間
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YearEnd {
+    pub start: MonthDay,
+    pub end: MonthDay,
+}
+
+impl Default for YearEnd {
+    fn default() -> Self {
+        Self {
+            start: MonthDay::new(12, 29),
+            end: MonthDay::new(1, 3),
+        }
+    }
+}
+
+impl YearEnd {
+    pub fn contains(&self, date: &NaiveDate) -> bool {
+        MonthDay::from(date).within(&self.start, &self.end)
+    }
+}
+
+impl RestaurantInfo {
+    /// 定休日なら true
+    pub fn is_closed(&self, date: &NaiveDate, opts: &SearchOptions) -> bool {
+        self.regular_holiday.iter().any(|r| r.is_closed(date, opts))
+    }
+
+    /// 隔週の休みなど、休みかもしれない日なら true
+    pub fn may_be_closed(&self, date: &NaiveDate) -> bool {
+        self.regular_holiday.iter().any(|r| r.may_be_closed(date))
+    }
+
+    /// `date` の検索に使う営業時間と、その説明
+    ///
+    /// 期間限定の営業時間しかなく、`date` がその期間外ならもう一方を使う。
+    pub fn schedule(
+        &self,
+        date: &NaiveDate,
+        opts: &SearchOptions,
+    ) -> (&[BussinessHours], Option<&str>) {
+        let primary = (
+            &self.business_hours[..],
+            self.business_hours_label.as_deref(),
+        );
+
+        let alt = match &self.alt_business_hours {
+            Some(alt) => (&alt.business_hours[..], Some(alt.label.as_str())),
+            None => return primary,
+        };
+
+        let (first, second) = if opts.use_alt {
+            (alt, primary)
+        } else {
+            (primary, alt)
+        };
+
+        if first.0.iter().any(|bh| bh.is_valid_on(date)) {
+            first
+        } else {
+            second
+        }
+    }
+
+    /// `opts.cutoff` の締め切りまでの分数。営業時間外か、締め切りを過ぎていれば 0
+    ///
+    /// `opts.meal` を指定すると、その区分の営業時間だけを見る。
+    /// 区分の書かれていない営業時間はどの区分にも当てはまるとする。
+    pub fn time_to_close(&self, dt: &DateTime<FixedOffset>, opts: &SearchOptions) -> usize {
+        self.open_hours(dt, opts)
+            .filter(|bh| bh.is_meal(opts.meal.as_deref()))
+            .map(|bh| bh.time_to_close(dt, opts.cutoff))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 次に開店するまでの分数。今の営業日と翌営業日の営業時間から探し、なければ None
+    ///
+    /// 営業中かどうかは見ないので、ランチとディナーの間なら次のディナーまでの分数になる。
+    /// `opts.meal` の扱いは `time_to_close` と同じ。
+    pub fn time_to_open(&self, dt: &DateTime<FixedOffset>, opts: &SearchOptions) -> Option<usize> {
+        let today = business_date(dt);
+
+        [today, today.succ_opt().unwrap()]
+            .iter()
+            .filter(|date| !self.is_closed(date, opts))
+            .flat_map(|date| {
+                self.schedule(date, opts)
+                    .0
+                    .iter()
+                    .filter(|bh| bh.is_meal(opts.meal.as_deref()))
+                    .filter_map(move |bh| bh.time_to_open(dt, date))
+            })
+            .min()
+    }
+
+    /// `dt` の時点で営業中の営業時間。LO を過ぎていても閉店前なら含む
+    pub fn open_hours<'a>(
+        &'a self,
+        dt: &'a DateTime<FixedOffset>,
+        opts: &SearchOptions,
+    ) -> impl Iterator<Item = &'a BussinessHours> + 'a {
+        let date = business_date(dt);
+        let closed = self.is_closed(&date, opts);
+        self.schedule(&date, opts)
+            .0
+            .iter()
+            .filter(move |bh| !closed && bh.remaining(dt).is_some())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct BussinessHours {
+    pub day_of_week: Option<String>,
+    /// ランチ、ディナーなど。`MEALS` のどれか
+    pub meal: Option<String>,
+    pub open: Time,
+    pub close: Time,
+    pub lo: Option<Time>,
+    /// 期間限定の営業時間なら、その初日
+    pub from: Option<MonthDay>,
+    /// 期間限定の営業時間なら、その最終日
+    pub until: Option<MonthDay>,
+    /// 「（立ち飲み）」など、営業時間に添えられた注記
+    pub note: Option<String>,
+}
+
+impl BussinessHours {
+    pub fn is_valid_on(&self, date: &NaiveDate) -> bool {
+        let md = MonthDay::from(date);
+        match (&self.from, &self.until) {
+            (Some(from), Some(until)) => md.within(from, until),
+            (from, until) => from.is_none_or(|f| f <= md) && until.is_none_or(|u| md <= u),
+        }
+    }
+
+    pub fn is_meal(&self, meal: Option<&str>) -> bool {
+        match (meal, &self.meal) {
+            (Some(m), Some(n)) => m == n,
+            _ => true,
+        }
+    }
+
+    /// `date` にこの営業時間で営業するなら true。定休日は見ない
+    pub fn applies_on(&self, date: &NaiveDate) -> bool {
+        if !self.is_valid_on(date) {
+            return false;
+        }
+
+        match self.day_of_week.as_deref() {
+            Some("祝") => is_holiday(date),
+            Some(w) => w == jp_weekday_name(date.weekday()),
+            None => true,
+        }
+    }
+
+    /// 営業中なら、LO と閉店までの残り時間
+    pub fn remaining(&self, dt: &DateTime<FixedOffset>) -> Option<Remaining> {
+        let (date, tm) = business_time(dt);
+
+        if !(self.applies_on(&date) && self.open <= tm && tm < self.close) {
+            return None;
+        }
+
+        Some(Remaining {
+            to_lo: self.lo.as_ref().map(|lo| lo.diff_min(&tm).max(0) as _),
+            to_close: self.close.diff_min(&tm) as _,
+        })
+    }
+
+    /// `cutoff` の締め切りまでの分数。営業時間外か、締め切りを過ぎていれば 0
+    pub fn time_to_close(&self, dt: &DateTime<FixedOffset>, cutoff: Cutoff) -> usize {
+        self.remaining(dt).map_or(0, |r| r.until(cutoff))
+    }
+
+    /// 営業日 `date` のこの営業時間が開くまでの分数。その日に営業しないか、もう開いていれば None
+    pub fn time_to_open(&self, dt: &DateTime<FixedOffset>, date: &NaiveDate) -> Option<usize> {
+        if !self.applies_on(date) {
+            return None;
+        }
+
+        let (today, tm) = business_time(dt);
+        let days = (*date - today).num_days();
+        let mins = days * 24 * 60 + self.open.diff_min(&tm) as i64;
+        if mins > 0 {
+            Some(mins as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// 営業日と、その日の 0 時から数えた時刻。早朝は前日の続きとして 24 時以降で数える
+fn business_time(dt: &DateTime<FixedOffset>) -> (NaiveDate, Time) {
+    let date = business_date(dt);
+    let time = dt.time();
+
+    let hour = if date != dt.date_naive() {
+        time.hour() + 24
+    } else {
+        time.hour()
+    };
+
+    (date, Time::new(hour as _, time.minute() as _))
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct Time {
+    pub hour: usize,
+    pub min: usize,
+}
+
+impl Time {
+    pub fn new(hour: usize, min: usize) -> Self {
+        Self { hour, min }
+    }
+
+    pub fn to_min(&self) -> usize {
+        self.hour * 60 + self.min
+    }
+
+    pub fn diff_min(&self, rhs: &Time) -> isize {
+        self.to_min() as isize - rhs.to_min() as isize
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{:02}", self.hour, self.min)
+    }
+}
+
+/// 年をまたいで繰り返す日付
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
+pub struct MonthDay {
+    pub month: u32,
+    pub day: u32,
+}
+
+impl MonthDay {
+    pub fn new(month: u32, day: u32) -> Self {
+        Self { month, day }
+    }
+
+    /// 翌日
+    pub fn succ(&self) -> Self {
+        // 2/29 を含むようにうるう年で計算する
+        NaiveDate::from_ymd_opt(2000, self.month, self.day)
+            .and_then(|d| d.succ_opt())
+            .map(|d| MonthDay::from(&d))
+            .unwrap_or_else(|| MonthDay::new(self.month % 12 + 1, 1))
+    }
+
+    /// `from` から `until` までに入っていれば true。`from` が後なら年をまたぐ
+    pub fn within(&self, from: &MonthDay, until: &MonthDay) -> bool {
+        if from <= until {
+            from <= self && self <= until
+        } else {
+            from <= self || self <= until
+        }
+    }
+}
+
+impl From<&NaiveDate> for MonthDay {
+    fn from(date: &NaiveDate) -> Self {
+        Self::new(date.month(), date.day())
+    }
+}
+
+impl FromStr for MonthDay {
+    type Err = String;
+
+    /// "12/29" の形式
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (m, d) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("invalid date: {}", s))?;
+        let m = m.parse().map_err(|_| format!("invalid month: {}", s))?;
+        let d = d.parse().map_err(|_| format!("invalid day: {}", s))?;
+        NaiveDate::from_ymd_opt(2000, m, d)
+            .map(|d| MonthDay::from(&d))
+            .ok_or_else(|| format!("invalid date: {}", s))
+    }
+}
+
+impl fmt::Display for MonthDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.month, self.day)
+    }
+}
+
+/// 営業日。早朝 5 時までは前日扱いにする
+pub fn business_date(dt: &DateTime<FixedOffset>) -> NaiveDate {
+    let date = dt.date_naive();
+    if (dt.hour(), dt.minute()) < (5, 0) {
+        date.pred_opt().unwrap()
+    } else {
+        date
+    }
+}
+
+pub fn jp_weekday_name(wd: Weekday) -> &'static str {
+    match wd {
+        Weekday::Mon => "月",
+        Weekday::Tue => "火",
+        Weekday::Wed => "水",
+        Weekday::Thu => "木",
+        Weekday::Fri => "金",
+        Weekday::Sat => "土",
+        Weekday::Sun => "日",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(&format!("{}:00+09:00", s)).unwrap()
+    }
+
+    /// "11:00-22:00"、LO があれば "11:00-22:00/21:30"
+    fn bh(day_of_week: Option<&str>, meal: Option<&str>, hours: &str) -> BussinessHours {
+        let time = |s: &str| {
+            let (h, m) = s.split_once(':').unwrap();
+            Time::new(h.parse().unwrap(), m.parse().unwrap())
+        };
+        let (range, lo) = match hours.split_once('/') {
+            Some((range, lo)) => (range, Some(time(lo))),
+            None => (hours, None),
+        };
+        let (open, close) = range.split_once('-').unwrap();
+
+        BussinessHours {
+            day_of_week: day_of_week.map(|s| s.to_string()),
+            meal: meal.map(|s| s.to_string()),
+            open: time(open),
+            close: time(close),
+            lo,
+            ..Default::default()
+        }
+    }
+
+    fn shop(
+        business_hours: Vec<BussinessHours>,
+        regular_holiday: Vec<HolidayRule>,
+    ) -> RestaurantInfo {
+        RestaurantInfo {
+            code: 0,
+            name: String::new(),
+            course: "A".to_string(),
+            url: String::new(),
+            tn_url: String::new(),
+            address: String::new(),
+            address_parts: None,
+            business_hours,
+            business_hours_label: None,
+            alt_business_hours: None,
+            business_hours_raw: String::new(),
+            regular_holiday,
+            irregular_holiday: false,
+            regular_holiday_raw: String::new(),
+            caveats: vec![],
+            normalize_rules: vec![],
+            phone: None,
+            seats: None,
+            smoking: None,
+            links: vec![],
+            menu: None,
+            location: None,
+        }
+    }
+
+    fn time_to_open(r: &RestaurantInfo, s: &str) -> Option<usize> {
+        r.time_to_open(&dt(s), &SearchOptions::default())
+    }
+
+    // 2020-09-02 は水曜、2020-09-21 は敬老の日、2020-09-22 は秋分の日
+
+    #[test]
+    fn opens_soon() {
+        let r = shop(vec![bh(None, Some("ランチ"), "11:30-14:30/14:00")], vec![]);
+        assert_eq!(time_to_open(&r, "2020-09-02T11:20"), Some(10));
+        assert_eq!(
+            r.time_to_close(&dt("2020-09-02T11:20"), &SearchOptions::default()),
+            0
+        );
+        // 開いていれば翌日の開店まで
+        assert_eq!(time_to_open(&r, "2020-09-02T12:00"), Some(23 * 60 + 30));
+    }
+
+    #[test]
+    fn opens_after_business_day_boundary() {
+        let r = shop(vec![bh(None, None, "11:00-22:00")], vec![]);
+        // 3 時は 9/2 の営業日の続き。開くのは 9/3 の 11 時
+        assert_eq!(time_to_open(&r, "2020-09-03T03:00"), Some(8 * 60));
+        assert_eq!(time_to_open(&r, "2020-09-03T05:00"), Some(6 * 60));
+    }
+
+    #[test]
+    fn opens_on_holidays_only() {
+        let r = shop(vec![bh(Some("祝"), None, "11:00-15:00")], vec![]);
+        assert_eq!(time_to_open(&r, "2020-09-21T10:00"), Some(60));
+        // 翌日の秋分の日
+        assert_eq!(time_to_open(&r, "2020-09-21T20:00"), Some(15 * 60));
+        assert_eq!(time_to_open(&r, "2020-09-23T10:00"), None);
+    }
+
+    #[test]
+    fn skips_regular_holiday() {
+        let tuesday = HolidayRule::Weekday {
+            weekday: "火".to_string(),
+            next_if_holiday: false,
+        };
+        let r = shop(vec![bh(None, None, "11:00-22:00")], vec![tuesday]);
+        assert_eq!(time_to_open(&r, "2020-09-07T23:00"), None);
+        assert_eq!(time_to_open(&r, "2020-09-08T23:00"), Some(12 * 60));
+    }
+
+    #[test]
+    fn opens_for_dinner() {
+        let r = shop(
+            vec![
+                bh(None, Some("ランチ"), "11:30-14:30/14:00"),
+                bh(None, Some("ディナー"), "17:30-22:00/21:30"),
+            ],
+            vec![],
+        );
+        assert_eq!(time_to_open(&r, "2020-09-02T15:30"), Some(120));
+
+        // ランチだけで探すと翌日のランチ
+        let opts = SearchOptions {
+            meal: Some("ランチ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            r.time_to_open(&dt("2020-09-02T15:30"), &opts),
+            Some(20 * 60)
+        );
+    }
+
+    fn remaining(hours: &str, s: &str) -> Option<Remaining> {
+        bh(None, None, hours).remaining(&dt(s))
+    }
+
+    #[test]
+    fn cutoffs() {
+        let r = remaining("11:00-22:00/21:30", "2020-09-02T21:00").unwrap();
+        assert_eq!(
+            r,
+            Remaining {
+                to_lo: Some(30),
+                to_close: 60,
+            }
+        );
+        assert_eq!(r.until(Cutoff::BeforeLastOrder(10)), 20);
+        assert_eq!(r.until(Cutoff::LastOrder), 30);
+        assert_eq!(r.until(Cutoff::Close), 60);
+        // 歩いているうちに LO を過ぎる
+        assert_eq!(r.until(Cutoff::BeforeLastOrder(40)), 0);
+    }
+
+    #[test]
+    fn cutoffs_without_lo() {
+        let r = remaining("11:00-15:00", "2020-09-02T14:40").unwrap();
+        assert_eq!(r.to_lo, None);
+        assert_eq!(r.until(Cutoff::BeforeLastOrder(10)), 10);
+        assert_eq!(r.until(Cutoff::LastOrder), 20);
+        assert_eq!(r.until(Cutoff::Close), 20);
+    }
+
+    #[test]
+    fn after_lo_before_close() {
+        let r = remaining("11:00-22:00/21:30", "2020-09-02T21:45").unwrap();
+        assert_eq!(
+            r,
+            Remaining {
+                to_lo: Some(0),
+                to_close: 15,
+            }
+        );
+
+        // 営業中の営業時間には入るが、LO で締め切ると営業中とはみなさない
+        let shop = shop(vec![bh(None, None, "11:00-22:00/21:30")], vec![]);
+        let now = dt("2020-09-02T21:45");
+        let opts = |cutoff| SearchOptions {
+            cutoff,
+            ..Default::default()
+        };
+        assert_eq!(shop.open_hours(&now, &opts(Cutoff::LastOrder)).count(), 1);
+        assert_eq!(shop.time_to_close(&now, &opts(Cutoff::LastOrder)), 0);
+        assert_eq!(shop.time_to_close(&now, &opts(Cutoff::Close)), 15);
+    }
+
+    #[test]
+    fn remaining_outside_hours() {
+        assert_eq!(remaining("11:00-22:00/21:30", "2020-09-02T10:59"), None);
+        assert_eq!(remaining("11:00-22:00/21:30", "2020-09-02T22:00"), None);
+        // 深夜の営業は前日の続き
+        assert_eq!(
+            remaining("17:00-26:00/25:30", "2020-09-03T01:00"),
+            Some(Remaining {
+                to_lo: Some(30),
+                to_close: 60,
+            })
+        );
+    }
+}
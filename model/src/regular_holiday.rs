@@ -0,0 +1,186 @@
+//! 店の定休日の決まり
+
+use crate::{is_holiday, jp_weekday_name, MonthDay, SearchOptions};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HolidayRule {
+    /// 毎週の曜日。"月"〜"日"
+    Weekday {
+        weekday: String,
+        /// 「祝日の場合は翌日」
+        #[serde(default)]
+        next_if_holiday: bool,
+    },
+    /// 第 n 週の曜日。「第2・第4火曜」なら nth は [2, 4]
+    NthWeekday { weekday: String, nth: Vec<u32> },
+    /// 隔週の曜日。どの週かはわからない
+    Biweekly { weekday: String },
+    /// 祝日
+    Holiday,
+    /// 祝日の翌日
+    DayAfterHoliday,
+    /// `SearchOptions::year_end` の期間
+    YearEnd,
+    /// 毎年決まった日。一日だけなら from と until は同じ
+    Dates { from: MonthDay, until: MonthDay },
+    /// 「2020年 8/13〜8/16」のように年の書かれた、その年だけの休み
+    Period { from: NaiveDate, until: NaiveDate },
+}
+
+impl HolidayRule {
+    /// `date` が休みなら true
+    pub fn is_closed(&self, date: &NaiveDate, opts: &SearchOptions) -> bool {
+        let weekday = jp_weekday_name(date.weekday());
+
+        match self {
+            HolidayRule::Weekday {
+                weekday: w,
+                next_if_holiday: false,
+            } => w == weekday,
+            HolidayRule::Weekday {
+                weekday: w,
+                next_if_holiday: true,
+            } => {
+                // 祝日に当たれば翌日に休む
+                let prev = date.pred_opt().unwrap();
+                w == weekday && !is_holiday(date)
+                    || w == jp_weekday_name(prev.weekday()) && is_holiday(&prev)
+            }
+            HolidayRule::NthWeekday { weekday: w, nth } => {
+                w == weekday && nth.contains(&((date.day() - 1) / 7 + 1))
+            }
+            HolidayRule::Biweekly { .. } => false,
+            HolidayRule::Holiday => is_holiday(date),
+            HolidayRule::DayAfterHoliday => {
+                is_holiday(&date.pred_opt().unwrap()) && !is_holiday(date)
+            }
+            HolidayRule::YearEnd => opts.year_end.contains(date),
+            HolidayRule::Dates { from, until } => MonthDay::from(date).within(from, until),
+            HolidayRule::Period { from, until } => from <= date && date <= until,
+        }
+    }
+
+    /// 休みかどうか決まらない日なら true
+    pub fn may_be_closed(&self, date: &NaiveDate) -> bool {
+        match self {
+            HolidayRule::Biweekly { weekday } => weekday == jp_weekday_name(date.weekday()),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn closed(rule: &HolidayRule, y: i32, m: u32, d: u32) -> bool {
+        rule.is_closed(&ymd(y, m, d), &SearchOptions::default())
+    }
+
+    fn weekday(w: &str, next_if_holiday: bool) -> HolidayRule {
+        HolidayRule::Weekday {
+            weekday: w.to_string(),
+            next_if_holiday,
+        }
+    }
+
+    #[test]
+    fn weekday_rule() {
+        let r = weekday("月", false);
+        assert!(closed(&r, 2021, 9, 13));
+        // 祝日でも休む
+        assert!(closed(&r, 2021, 9, 20));
+        assert!(!closed(&r, 2021, 9, 14));
+    }
+
+    #[test]
+    fn weekday_next_if_holiday() {
+        let r = weekday("月", true);
+        assert!(closed(&r, 2021, 9, 13));
+        assert!(!closed(&r, 2021, 9, 14));
+        // 敬老の日の月曜は開けて、翌日に休む
+        assert!(!closed(&r, 2021, 9, 20));
+        assert!(closed(&r, 2021, 9, 21));
+    }
+
+    #[test]
+    fn nth_weekday() {
+        let r = HolidayRule::NthWeekday {
+            weekday: "火".to_string(),
+            nth: vec![2, 4],
+        };
+        assert!(!closed(&r, 2020, 9, 1));
+        assert!(closed(&r, 2020, 9, 8));
+        assert!(!closed(&r, 2020, 9, 15));
+        // 第4火曜の秋分の日も休む
+        assert!(closed(&r, 2020, 9, 22));
+        assert!(!closed(&r, 2020, 9, 29));
+        assert!(!closed(&r, 2020, 9, 9));
+    }
+
+    #[test]
+    fn biweekly() {
+        let r = HolidayRule::Biweekly {
+            weekday: "水".to_string(),
+        };
+        assert!(!closed(&r, 2020, 9, 2));
+        assert!(r.may_be_closed(&ymd(2020, 9, 2)));
+        assert!(!r.may_be_closed(&ymd(2020, 9, 3)));
+    }
+
+    #[test]
+    fn holiday() {
+        let r = HolidayRule::Holiday;
+        assert!(closed(&r, 2020, 9, 22));
+        assert!(!closed(&r, 2020, 9, 23));
+    }
+
+    #[test]
+    fn day_after_holiday() {
+        let r = HolidayRule::DayAfterHoliday;
+        assert!(closed(&r, 2021, 9, 21));
+        assert!(!closed(&r, 2021, 9, 20));
+        // 翌日も祝日なら、その次の日ではなくその日は開ける
+        assert!(!closed(&r, 2020, 9, 22));
+        assert!(closed(&r, 2020, 9, 23));
+    }
+
+    #[test]
+    fn year_end() {
+        let r = HolidayRule::YearEnd;
+        assert!(!closed(&r, 2020, 12, 28));
+        assert!(closed(&r, 2020, 12, 29));
+        assert!(closed(&r, 2021, 1, 3));
+        assert!(!closed(&r, 2021, 1, 4));
+    }
+
+    #[test]
+    fn dates_every_year() {
+        let r = HolidayRule::Dates {
+            from: MonthDay::new(8, 13),
+            until: MonthDay::new(8, 16),
+        };
+        assert!(closed(&r, 2020, 8, 13));
+        assert!(closed(&r, 2021, 8, 16));
+        assert!(!closed(&r, 2021, 8, 17));
+    }
+
+    #[test]
+    fn period_only_that_year() {
+        let r = HolidayRule::Period {
+            from: ymd(2020, 8, 13),
+            until: ymd(2020, 8, 16),
+        };
+        assert!(closed(&r, 2020, 8, 13));
+        assert!(closed(&r, 2020, 8, 16));
+        assert!(!closed(&r, 2020, 8, 17));
+        assert!(!closed(&r, 2021, 8, 14));
+    }
+}
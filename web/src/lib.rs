@@ -1,109 +1,29 @@
-#![recursion_limit = "1024"]
-
-use chrono::{Date, DateTime, Datelike, FixedOffset, Timelike, Utc, Weekday};
+#![recursion_limit = "2048"]
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike, Utc};
+use kanda_model::{
+    business_date,
+    event::EventConfig,
+    holiday::{set_calendar, Holiday, HolidayCalendar},
+    BussinessHours, CaveatFlag, Cutoff, Remaining, RestaurantInfo, SearchOptions, MEALS,
+};
 use log::*;
-use serde::{Deserialize, Serialize};
 use std::{
     cmp::{max, min},
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     sync::RwLock,
 };
+use storage::{JournalEntry, Progress, Visit};
 use wasm_bindgen::{prelude::*, JsCast};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Request, RequestInit, RequestMode, Response};
-use yew::prelude::*;
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct RestaurantInfo {
-    code: usize,
-    name: String,
-    course: String,
-    url: String,
-    tn_url: String,
-    address: String,
-    business_hours: Vec<BussinessHours>,
-    business_hours_raw: String,
-    regular_holiday: Vec<String>,
-    regular_holiday_raw: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct BussinessHours {
-    day_of_week: Option<String>,
-    open: Time,
-    close: Time,
-    lo: Option<Time>,
-}
-
-impl BussinessHours {
-    fn time_to_close(&self, dt: &DateTime<FixedOffset>) -> usize {
-        let date = dt.date();
-        let time = dt.time();
-
-        let hour = time.hour();
-        let minute = time.minute();
-
-        // 早朝は前日扱いにする
-        let (hour, minute, date) = if (hour, minute) < (5, 0) {
-            (hour + 24, minute, date.pred())
-        } else {
-            (hour, minute, date)
-        };
-
-        let wd = jp_weekday_name(date.weekday());
-        let holiday = is_holiday(&date);
-
-        // info!("{:?}, {:?}", self, dt);
-
-        if let Some(w) = &self.day_of_week {
-            if w == "祝" {
-                if !holiday {
-                    return 0;
-                }
-            } else {
-                if w != wd {
-                    return 0;
-                }
-            }
-        }
-
-        let tm = Time::new(hour as _, minute as _);
-
-        if let Some(lo) = &self.lo {
-            if self.open <= tm && &tm < lo {
-                lo.diff_min(&tm) as _
-            } else {
-                0
-            }
-        } else {
-            if self.open <= tm && tm < self.close {
-                self.close.diff_min(&tm) as _
-            } else {
-                0
-            }
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq)]
-struct Time {
-    hour: usize,
-    min: usize,
-}
-
-impl Time {
-    fn new(hour: usize, min: usize) -> Self {
-        Self { hour, min }
-    }
-
-    fn to_min(&self) -> usize {
-        self.hour * 60 + self.min
-    }
+use yew::{
+    prelude::*,
+    services::reader::{FileData, ReaderService, ReaderTask},
+};
 
-    fn diff_min(&self, rhs: &Time) -> isize {
-        self.to_min() as isize - rhs.to_min() as isize
-    }
-}
+mod photos;
+mod storage;
 
 struct Model {}
 
@@ -123,10 +43,12 @@ impl Component for Model {
     }
 
     fn view(&self) -> Html {
+        let event = EVENT.read().unwrap();
+
         html! {
             <>
             <div class="jumbotron text-center">
-                <h1>{"神田カレーグランプリ スタンプラリー2020"}</h1>
+                <h1>{ &event.title }</h1>
                 <p class="lead">{"🍛営業中店舗検索ツール🍛"}</p>
             </div>
 
@@ -134,7 +56,7 @@ impl Component for Model {
             <div class="col">
                 <p>
                     <div class="text-center">
-                    <a href="https://kanda-curry.com/?page_id=12180">{"神田カレーグランプリ スタンプラリー2020"}</a>
+                    <a href=event.index_url.clone()>{ &event.title }</a>
                     {" の営業中店舗を検索するツールです。"}
                     </div>
                     <hr/>
@@ -156,11 +78,46 @@ impl Component for Model {
 
 lazy_static::lazy_static! {
     static ref RESTAURANT_INFO: RwLock<Vec<RestaurantInfo>> = RwLock::new(vec![]);
+    static ref EVENT: RwLock<EventConfig> = RwLock::new(EventConfig::default());
 }
 
 struct MainComponent {
     link: ComponentLink<Self>,
     props: Props,
+    reader: ReaderService,
+    /// 読み込み中の引き継ぎファイル
+    reader_task: Option<ReaderTask>,
+    /// 読み込んだ日記の写真の object URL
+    photo_urls: HashMap<u64, String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Page {
+    Search,
+    Journal,
+}
+
+/// 書きかけの日記
+#[derive(Clone)]
+struct JournalDraft {
+    shop: Option<usize>,
+    /// None なら保存した時刻
+    time: Option<DateTime<FixedOffset>>,
+    rating: u8,
+    notes: String,
+    photo: Option<web_sys::File>,
+}
+
+impl Default for JournalDraft {
+    fn default() -> Self {
+        Self {
+            shop: None,
+            time: None,
+            rating: 3,
+            notes: String::new(),
+            photo: None,
+        }
+    }
 }
 
 #[derive(Properties, Clone)]
@@ -171,15 +128,59 @@ struct Props {
     selected_courses: Vec<bool>,
     #[prop_or(false)]
     include_visited: bool,
+    #[prop_or(default_search_options())]
+    opts: SearchOptions,
     #[prop_or(get_visited())]
     visited: Vec<bool>,
+    /// カレーの値段の上限
+    #[prop_or(None)]
+    max_price: Option<u32>,
+    /// この分数以内に開店する店を「まもなく開店」に出す
+    #[prop_or(30)]
+    open_soon_minutes: usize,
+    /// 店まで歩く時間。`Cutoff::BeforeLastOrder` に使う
+    #[prop_or(10)]
+    walk_minutes: usize,
+    /// 店を町名ごとにまとめて表示する
+    #[prop_or(false)]
+    group_by_town: bool,
+    /// 引き継ぎ用の QR コードを表示する
+    #[prop_or(false)]
+    show_transfer: bool,
+    #[prop_or(Page::Search)]
+    page: Page,
+    #[prop_or_default]
+    journal_draft: JournalDraft,
 }
 
 enum Msg {
     DateTime(ChangeData),
-    SelectCourse(char),
+    SelectCourse(usize),
     Visited(usize),
     IncludeVisited,
+    SelectMeal(ChangeData),
+    UseAlt,
+    YearEndStart(ChangeData),
+    YearEndEnd(ChangeData),
+    MaxPrice(ChangeData),
+    OpenSoonMinutes(ChangeData),
+    Cutoff(ChangeData),
+    WalkMinutes(ChangeData),
+    GroupByTown,
+    ShowTransfer,
+    ImportFile(ChangeData),
+    Imported(FileData),
+    ShowPage(Page),
+    Record(usize),
+    JournalShop(ChangeData),
+    JournalTime(ChangeData),
+    JournalRating(u8),
+    JournalNotes(InputData),
+    JournalPhoto(ChangeData),
+    SaveJournal,
+    DeleteJournal(u64),
+    LoadPhotos,
+    PhotoLoaded(u64, String),
 }
 
 impl Component for MainComponent {
@@ -187,7 +188,13 @@ impl Component for MainComponent {
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        MainComponent { link, props }
+        MainComponent {
+            link,
+            props,
+            reader: ReaderService::new(),
+            reader_task: None,
+            photo_urls: HashMap::new(),
+        }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
@@ -197,8 +204,7 @@ impl Component for MainComponent {
                 info!("value: {}", s);
                 self.props.dt = dbg!(DateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M%z").unwrap());
             }
-            Msg::SelectCourse(c) => {
-                let ix = "ABCDE".find(c).unwrap();
+            Msg::SelectCourse(ix) => {
                 self.props.selected_courses[ix] = !self.props.selected_courses[ix];
                 set_selected_course(&self.props.selected_courses)
             }
@@ -209,6 +215,145 @@ impl Component for MainComponent {
             Msg::IncludeVisited => {
                 self.props.include_visited = !self.props.include_visited;
             }
+            Msg::SelectMeal(ChangeData::Select(sel)) => {
+                let meal = sel.value();
+                self.props.opts.meal = if meal.is_empty() { None } else { Some(meal) };
+            }
+            Msg::UseAlt => {
+                self.props.opts.use_alt = !self.props.opts.use_alt;
+            }
+            Msg::YearEndStart(ChangeData::Value(s)) => {
+                if let Ok(md) = s.parse() {
+                    self.props.opts.year_end.start = md;
+                }
+            }
+            Msg::YearEndEnd(ChangeData::Value(s)) => {
+                if let Ok(md) = s.parse() {
+                    self.props.opts.year_end.end = md;
+                }
+            }
+            Msg::MaxPrice(ChangeData::Value(s)) => {
+                self.props.max_price = s.trim().parse().ok();
+            }
+            Msg::OpenSoonMinutes(ChangeData::Value(s)) => {
+                if let Ok(n) = s.trim().parse() {
+                    self.props.open_soon_minutes = n;
+                }
+            }
+            Msg::Cutoff(ChangeData::Select(sel)) => {
+                self.props.opts.cutoff = match sel.value().as_str() {
+                    "before-lo" => Cutoff::BeforeLastOrder(self.props.walk_minutes),
+                    "close" => Cutoff::Close,
+                    _ => Cutoff::LastOrder,
+                };
+            }
+            Msg::WalkMinutes(ChangeData::Value(s)) => {
+                if let Ok(n) = s.trim().parse() {
+                    self.props.walk_minutes = n;
+                    if let Cutoff::BeforeLastOrder(_) = self.props.opts.cutoff {
+                        self.props.opts.cutoff = Cutoff::BeforeLastOrder(n);
+                    }
+                }
+            }
+            Msg::GroupByTown => {
+                self.props.group_by_town = !self.props.group_by_town;
+            }
+            Msg::ShowTransfer => {
+                self.props.show_transfer = !self.props.show_transfer;
+            }
+            Msg::ImportFile(ChangeData::Files(files)) => {
+                if let Some(file) = files.get(0) {
+                    let callback = self.link.callback(Msg::Imported);
+                    self.reader_task = self.reader.read_file(file, callback).ok();
+                }
+                return false;
+            }
+            Msg::Imported(data) => {
+                self.reader_task = None;
+                let p = String::from_utf8(data.content)
+                    .map_err(|e| e.to_string())
+                    .and_then(|s| storage::from_json(&s));
+                match p {
+                    Ok(p) => {
+                        if !import_progress(p) {
+                            return false;
+                        }
+                        self.props.visited = get_visited();
+                        self.props.selected_courses = get_selected_course();
+                    }
+                    Err(e) => alert(&format!("読み込めませんでした: {}", e)),
+                }
+            }
+            Msg::ShowPage(page) => {
+                self.props.page = page;
+                if page == Page::Journal {
+                    self.link.send_message(Msg::LoadPhotos);
+                }
+            }
+            Msg::Record(i) => {
+                self.props.journal_draft = JournalDraft {
+                    shop: Some(i),
+                    ..JournalDraft::default()
+                };
+                self.link.send_message(Msg::ShowPage(Page::Journal));
+            }
+            Msg::JournalShop(ChangeData::Select(sel)) => {
+                self.props.journal_draft.shop = sel.value().parse().ok();
+            }
+            Msg::JournalTime(ChangeData::Value(s)) => {
+                let s = format!("{}+0900", s);
+                self.props.journal_draft.time =
+                    DateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M%z").ok();
+            }
+            Msg::JournalRating(rating) => {
+                self.props.journal_draft.rating = rating;
+            }
+            Msg::JournalNotes(ev) => {
+                self.props.journal_draft.notes = ev.value;
+                return false;
+            }
+            Msg::JournalPhoto(ChangeData::Files(files)) => {
+                self.props.journal_draft.photo = files.get(0);
+                return false;
+            }
+            Msg::SaveJournal => self.save_journal(),
+            Msg::DeleteJournal(id) => {
+                let mut p = storage::load();
+                if let Some(e) = p.journal.iter().find(|e| e.id == id) {
+                    if e.has_photo {
+                        let key = e.photo_key();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            if let Err(e) = photos::delete(&key).await {
+                                error!("failed to delete photo: {:?}", e);
+                            }
+                        });
+                    }
+                }
+                p.journal.retain(|e| e.id != id);
+                storage::save(&p);
+            }
+            Msg::LoadPhotos => {
+                for e in storage::load().journal {
+                    if !e.has_photo || self.photo_urls.contains_key(&e.id) {
+                        continue;
+                    }
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match photos::get(&e.photo_key()).await {
+                            Ok(Some(blob)) => {
+                                let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
+                                link.send_message(Msg::PhotoLoaded(e.id, url));
+                            }
+                            Ok(None) => {}
+                            Err(err) => error!("failed to load photo: {:?}", err),
+                        }
+                    });
+                }
+                return false;
+            }
+            Msg::PhotoLoaded(id, url) => {
+                self.photo_urls.insert(id, url);
+            }
             _ => unreachable!(),
         }
         true
@@ -219,19 +364,38 @@ impl Component for MainComponent {
     }
 
     fn view(&self) -> Html {
-        let dt = &self.props.dt;
+        if self.props.page == Page::Journal {
+            return html! {
+                <>
+                { self.nav_view() }
+                { self.journal_view() }
+                </>
+            };
+        }
 
-        let weekday = jp_weekday_name(dt.weekday());
-        let holiday = is_holiday(&dt.date());
+        let dt = &self.props.dt;
 
         // info!("Cur time: {:?}", dt);
 
         let lock = RESTAURANT_INFO.read().unwrap();
+        let event = EVENT.read().unwrap();
+        let is_selected = |r: &RestaurantInfo| {
+            event
+                .course_index(&r.course)
+                .is_some_and(|ix| self.props.selected_courses[ix])
+        };
+        // 値段のわからない店は残す
+        let within_budget = |r: &RestaurantInfo| {
+            let price = r.menu.as_ref().and_then(|m| m.price);
+            self.props
+                .max_price
+                .is_none_or(|max| price.is_none_or(|p| p <= max))
+        };
 
-        let visited = lock.iter().enumerate().filter(|(i, r)| {
-            let ix = "ABCDE".find(&r.course).unwrap();
-            self.props.visited[*i] && self.props.selected_courses[ix]
-        });
+        let visited = lock
+            .iter()
+            .enumerate()
+            .filter(|(i, r)| self.props.visited[*i] && is_selected(r));
 
         let seatch_target_cnt = lock
             .iter()
@@ -240,8 +404,7 @@ impl Component for MainComponent {
                 if !self.props.include_visited && self.props.visited[*i] {
                     return false;
                 }
-                let ix = "ABCDE".find(&r.course).unwrap();
-                self.props.selected_courses[ix]
+                is_selected(r) && within_budget(r)
             })
             .count();
 
@@ -252,26 +415,23 @@ impl Component for MainComponent {
                 if !self.props.include_visited && self.props.visited[*i] {
                     return false;
                 }
-                let ix = "ABCDE".find(&r.course).unwrap();
-                self.props.selected_courses[ix]
-            })
-            .map(|(i, r)| {
-                let is_closed = r
-                    .regular_holiday
-                    .iter()
-                    .any(|r| r == weekday || r == "祝" && holiday);
-
-                let time_to_close = if is_closed {
-                    0
-                } else {
-                    let bh = &r.business_hours;
-                    bh.iter().map(|bh| bh.time_to_close(&dt)).max().unwrap_or(0)
-                };
-
-                (i, r, time_to_close)
+                is_selected(r) && within_budget(r)
             })
+            .map(|(i, r)| (i, r, r.time_to_close(dt, &self.props.opts)))
             .partition(|(_, _, time_to_close)| *time_to_close > 0);
 
+        let open_soon_minutes = self.props.open_soon_minutes;
+        let time_to_open = |r: &RestaurantInfo| {
+            r.time_to_open(dt, &self.props.opts)
+                .filter(|m| *m <= open_soon_minutes)
+        };
+
+        // 営業時間外の店のうち、まもなく開店する店を開店の早い順に
+        let (mut open_soon, not_avails): (Vec<_>, Vec<_>) = not_avails
+            .into_iter()
+            .partition(|(_, r, _)| time_to_open(r).is_some());
+        open_soon.sort_by_key(|(_, r, _)| time_to_open(r));
+
         let mut status = BTreeMap::<String, (usize, usize)>::new();
         let mut free_course = 0;
 
@@ -287,18 +447,14 @@ impl Component for MainComponent {
 
         let cleard_course = max(
             status.iter().filter(|(_, (a, b))| a == b).count(),
-            if free_course >= 25 { 1 } else { 0 },
+            if free_course >= event.free_course_size {
+                1
+            } else {
+                0
+            },
         );
 
-        let degree = match cleard_course {
-            0 => "🥺未獲得",
-            1 => "🏅神田カレーマイスター",
-            2 => "🥉神田カレーブロンズマイスター",
-            3 => "🥈神田カレーシルバーマイスター",
-            4 => "🥇神田カレーゴールドマイスター",
-            5 => "👑神田カレーグランドマイスター",
-            _ => unreachable!(),
-        };
+        let degree = event.title_tier(cleard_course);
 
         let card = |i: usize, r: &RestaurantInfo, time_to_close: usize| {
             let s = r.business_hours_raw.replace("<br>", "\n");
@@ -309,14 +465,9 @@ impl Component for MainComponent {
 
             let address = r.address.replace("<br>", "\n");
 
-            let header_color = match r.course.as_str() {
-                "A" => "#e5407e",
-                "B" => "#0e80d0",
-                "C" => "#df7600",
-                "D" => "#50a639",
-                "E" => "#7d51a0",
-                _ => unreachable!(),
-            };
+            let header_color = event
+                .course_index(&r.course)
+                .map_or("gray", |ix| event.courses[ix].color.as_str());
 
             html! {
                 <div class="card">
@@ -339,23 +490,93 @@ impl Component for MainComponent {
                         </h5>
                         <p class="card-text">
                         <a href=format!("https://maps.google.co.jp/maps/search/{}",
-                            urlencoding::encode(&format!("{} {}", address, r.name)))
+                            urlencoding::encode(&format!("{} {}",
+                                r.address_parts.as_ref().map_or_else(|| address.clone(), |a| a.map_query()),
+                                r.name)))
                             class="text-secondary">{ address }</a>
                         </p>
+                        {
+                            for caveat_flags(r).into_iter().map(|(flag, text)| html! {
+                                <span class="badge badge-warning mr-1" title=text>{ flag.label() }</span>
+                            })
+                        }
+                        { menu_view(r) }
+                        <button type="button" class="btn btn-outline-secondary btn-sm mt-2"
+                            onclick=self.link.callback(move |_| Msg::Record(i))>
+                            {"カレー日記に記録"}
+                        </button>
                     </div>
 
                     <ul class="list-group list-group-flush">
                         {
-                            if time_to_close > 0 && time_to_close <= 30 {
+                            if let (Some(alt), (_, Some(label))) = (&r.alt_business_hours, r.schedule(&business_date(dt), &self.props.opts)) {
+                                let other = if label == alt.label {
+                                    r.business_hours_label.as_deref().unwrap_or("")
+                                } else {
+                                    &alt.label
+                                };
+                                html! {
+                                    <li class="list-group-item list-group-item-warning">
+                                        { format!("{}で検索しています（{}もあります）", label, other) }
+                                    </li>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        {
+                            if r.irregular_holiday {
                                 html! {
-                                    <li class="list-group-item text-white bg-danger">
-                                        { "まもなく営業終了" }
+                                    <li class="list-group-item list-group-item-warning">
+                                        { "不定休があります。来店前にご確認ください" }
                                     </li>
                                 }
                             } else {
                                 html! {}
                             }
                         }
+                        {
+                            if r.may_be_closed(&business_date(dt)) {
+                                html! {
+                                    <li class="list-group-item list-group-item-warning">
+                                        { "隔週で休む曜日です。来店前にご確認ください" }
+                                    </li>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        {
+                            match time_to_open(r) {
+                                Some(m) if time_to_close == 0 => html! {
+                                    <li class="list-group-item list-group-item-info">
+                                        { format!("{} 開店 あと{}分",
+                                            (*dt + chrono::Duration::minutes(m as i64)).format("%H:%M"), m) }
+                                    </li>
+                                },
+                                _ => html! {},
+                            }
+                        }
+
+                        {
+                            for r.open_hours(dt, &self.props.opts).map(|bh| {
+                                html! {
+                                    <>
+                                    <li class="list-group-item list-group-item-success">
+                                        { format!("{}営業中 {}〜{}{}",
+                                            bh.meal.as_deref().unwrap_or(""), bh.open, bh.close,
+                                            match (&bh.from, &bh.until) {
+                                                (None, None) => "".to_string(),
+                                                (from, until) => format!("（{}〜{}）",
+                                                    from.map(|d| d.to_string()).unwrap_or_default(),
+                                                    until.map(|d| d.to_string()).unwrap_or_default()),
+                                            }) }
+                                    </li>
+                                    { remaining_view(bh, dt, self.props.opts.cutoff) }
+                                    </>
+                                }
+                            })
+                        }
 
                         <li class="list-group-item">
                         { "営業時間：" }
@@ -367,11 +588,83 @@ impl Component for MainComponent {
                         { "定休日：" }
                         { rh }
                         </li>
+                        {
+                            if let Some(phone) = &r.phone {
+                                html! {
+                                    <li class="list-group-item">
+                                        { "電話：" }
+                                        <a href=format!("tel:{}", phone.replace('-', ""))>{ phone }</a>
+                                    </li>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        {
+                            if r.seats.is_some() || r.smoking.is_some() {
+                                let seats = r.seats.map(|n| format!("{}席", n));
+                                let smoking = r.smoking.map(|s| s.label().to_string());
+                                html! {
+                                    <li class="list-group-item">
+                                        { seats.into_iter().chain(smoking).collect::<Vec<_>>().join(" / ") }
+                                    </li>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        {
+                            if !r.links.is_empty() {
+                                html! {
+                                    <li class="list-group-item">
+                                        {
+                                            for r.links.iter().map(|l| html! {
+                                                <a href=l.url.clone() class="mr-2" target="_blank">{ &l.label }</a>
+                                            })
+                                        }
+                                    </li>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
                     </ul>
                 </div>
             }
         };
 
+        let cards = |rs: Vec<(usize, &RestaurantInfo, usize)>| {
+            if !self.props.group_by_town {
+                return html! {
+                    <div class="card-columns">
+                    { for rs.into_iter().map(|r| card(r.0, r.1, r.2)) }
+                    </div>
+                };
+            }
+
+            let mut towns = BTreeMap::<&str, Vec<_>>::new();
+            for r in rs {
+                let town =
+                    r.1.address_parts
+                        .as_ref()
+                        .map_or("その他", |a| a.neighbourhood());
+                towns.entry(town).or_default().push(r);
+            }
+
+            html! {
+                {
+                    for towns.into_iter().map(|(town, rs)| html! {
+                        <>
+                        <h5>{ format!("{} ({})", town, rs.len()) }</h5>
+                        <div class="card-columns">
+                        { for rs.into_iter().map(|r| card(r.0, r.1, r.2)) }
+                        </div>
+                        </>
+                    })
+                }
+            }
+        };
+
         let status = html! {
             <>
             <h2>{"コース制覇状況"}</h2>
@@ -395,8 +688,8 @@ impl Component for MainComponent {
 
             <tr>
                 <th scope="row">{ "フリーコース" }</th>
-                <td>{ format!("{} / 25", min(25, free_course)) }</td>
-                <td>{ if free_course >= 25 {"制覇！"} else {"未制覇"} }</td>
+                <td>{ format!("{} / {}", min(event.free_course_size, free_course), event.free_course_size) }</td>
+                <td>{ if free_course >= event.free_course_size {"制覇！"} else {"未制覇"} }</td>
             </tr>
 
             </tbody>
@@ -406,11 +699,13 @@ impl Component for MainComponent {
 
         html! {
             <>
+            { self.nav_view() }
 
             <div class="row d-flex">
 
             <div class="d-flex-column mr-4 ml-4">
             { status }
+            { self.transfer_view() }
             </div>
 
             <div class="col-lg mr-4">
@@ -426,9 +721,9 @@ impl Component for MainComponent {
                             onchange=self.link.callback(|ev| Msg::DateTime(ev))
                             value=format!(
                             "{}-{:02}-{:02}T{:02}:{:02}",
-                            self.props.dt.date().year(),
-                            self.props.dt.date().month(),
-                            self.props.dt.date().day(),
+                            self.props.dt.year(),
+                            self.props.dt.month(),
+                            self.props.dt.day(),
                             self.props.dt.time().hour(),
                             self.props.dt.time().minute(),
                         )/>
@@ -437,13 +732,13 @@ impl Component for MainComponent {
                 <div class="form-group row">
                     <label class="col-sm-2 col-form-label">{"コース"}</label>
                     {
-                        for "ABCDE".chars().enumerate().map(|(i, c)| {
+                        for event.courses.iter().map(|c| &c.name).enumerate().map(|(i, c)| {
                             let id = format!("checkbox-{}", c);
                             let checked = self.props.selected_courses[i];
                             html!{
                             <div class="form-check form-check-inline">
                                 <input class="form-check-input" type="checkbox" id=id checked=checked
-                                    onclick=self.link.callback(move |_| Msg::SelectCourse(c)) />
+                                    onclick=self.link.callback(move |_| Msg::SelectCourse(i)) />
                                 <label class="form-check-label" for=id>{format!("{}コース", c)}</label>
                             </div>
                             }
@@ -458,6 +753,90 @@ impl Component for MainComponent {
                             onclick=self.link.callback(|_| Msg::IncludeVisited) />
                         <label for="include-visited" class="form-check-label">{"訪問済み店舗を含める"}</label>
                     </div>
+                    <div class="form-check form-check-inline">
+                        <input class="form-check-input" type="checkbox" id="use-alt"
+                            checked=self.props.opts.use_alt
+                            onclick=self.link.callback(|_| Msg::UseAlt) />
+                        <label for="use-alt" class="form-check-label">{"通常の営業時間で検索する"}</label>
+                    </div>
+                    <div class="form-check form-check-inline">
+                        <input class="form-check-input" type="checkbox" id="group-by-town"
+                            checked=self.props.group_by_town
+                            onclick=self.link.callback(|_| Msg::GroupByTown) />
+                        <label for="group-by-town" class="form-check-label">{"町名ごとにまとめる"}</label>
+                    </div>
+                </div>
+                <div class="form-group row">
+                    <label for="year-end-start" class="col-sm-2 col-form-label">{"年末年始休業"}</label>
+                    <div class="col-sm-2">
+                        <input type="text" id="year-end-start" class="form-control"
+                            value=self.props.opts.year_end.start.to_string()
+                            onchange=self.link.callback(|ev| Msg::YearEndStart(ev)) />
+                    </div>
+                    <span class="col-form-label">{"〜"}</span>
+                    <div class="col-sm-2">
+                        <input type="text" id="year-end-end" class="form-control"
+                            value=self.props.opts.year_end.end.to_string()
+                            onchange=self.link.callback(|ev| Msg::YearEndEnd(ev)) />
+                    </div>
+                </div>
+                <div class="form-group row">
+                    <label for="meal" class="col-sm-2 col-form-label">{"時間帯"}</label>
+                    <div class="col-sm-4">
+                        <select id="meal" class="form-control"
+                            onchange=self.link.callback(|ev| Msg::SelectMeal(ev))>
+                            <option value="" selected=self.props.opts.meal.is_none()>{"指定なし"}</option>
+                            {
+                                for MEALS.iter().map(|&m| html! {
+                                    <option value=m selected=self.props.opts.meal.as_deref() == Some(m)>{m}</option>
+                                })
+                            }
+                        </select>
+                    </div>
+                </div>
+                <div class="form-group row">
+                    <label for="max-price" class="col-sm-2 col-form-label">{"予算"}</label>
+                    <div class="col-sm-2">
+                        <input type="number" id="max-price" class="form-control" min="0" step="100"
+                            placeholder="上限なし"
+                            value=self.props.max_price.map(|p| p.to_string()).unwrap_or_default()
+                            onchange=self.link.callback(|ev| Msg::MaxPrice(ev)) />
+                    </div>
+                    <span class="col-form-label">{"円まで（値段の載っていない店も表示）"}</span>
+                </div>
+                <div class="form-group row">
+                    <label for="open-soon" class="col-sm-2 col-form-label">{"まもなく開店"}</label>
+                    <div class="col-sm-2">
+                        <input type="number" id="open-soon" class="form-control" min="0" step="5"
+                            value=self.props.open_soon_minutes.to_string()
+                            onchange=self.link.callback(|ev| Msg::OpenSoonMinutes(ev)) />
+                    </div>
+                    <span class="col-form-label">{"分以内"}</span>
+                </div>
+                <div class="form-group row">
+                    <label for="cutoff" class="col-sm-2 col-form-label">{"営業中とみなす"}</label>
+                    <div class="col-sm-4">
+                        <select id="cutoff" class="form-control"
+                            onchange=self.link.callback(|ev| Msg::Cutoff(ev))>
+                            <option value="before-lo"
+                                selected=matches!(self.props.opts.cutoff, Cutoff::BeforeLastOrder(_))>
+                                {"LO の徒歩分前まで"}
+                            </option>
+                            <option value="lo" selected=self.props.opts.cutoff == Cutoff::LastOrder>
+                                {"LO まで"}
+                            </option>
+                            <option value="close" selected=self.props.opts.cutoff == Cutoff::Close>
+                                {"閉店まで"}
+                            </option>
+                        </select>
+                    </div>
+                    <label for="walk-minutes" class="col-sm-1 col-form-label">{"徒歩"}</label>
+                    <div class="col-sm-2">
+                        <input type="number" id="walk-minutes" class="form-control" min="0" step="1"
+                            value=self.props.walk_minutes.to_string()
+                            onchange=self.link.callback(|ev| Msg::WalkMinutes(ev)) />
+                    </div>
+                    <span class="col-form-label">{"分"}</span>
                 </div>
             </form>
 
@@ -466,18 +845,21 @@ impl Component for MainComponent {
             <h2>{ format!("営業中の店舗 ({}/{})", avails.len(), seatch_target_cnt) }</h2>
             <br/>
 
-            <div class="card-columns">
-            { for avails.into_iter().map(|r| card(r.0, r.1, r.2)) }
-            </div>
+            { cards(avails) }
+
+            <hr/>
+
+            <h2>{ format!("まもなく開店する店舗 ({}/{})", open_soon.len(), seatch_target_cnt) }</h2>
+            <br/>
+
+            { cards(open_soon) }
 
             <hr/>
 
             <h2>{ format!("営業時間外の店舗 ({}/{})", not_avails.len(), seatch_target_cnt) }</h2>
             <br/>
 
-            <div class="card-columns">
-            { for not_avails.into_iter().map(|r| card(r.0, r.1, r.2)) }
-            </div>
+            { cards(not_avails) }
 
             <hr/>
 
@@ -500,116 +882,482 @@ impl Component for MainComponent {
     fn destroy(&mut self) {}
 }
 
+/// グランプリのカレーの写真と名前、値段
+fn menu_view(r: &RestaurantInfo) -> Html {
+    let menu = match &r.menu {
+        Some(menu) => menu,
+        None => return html! {},
+    };
+
+    html! {
+        <div class="media mt-3">
+            {
+                if let Some(image) = &menu.image {
+                    html! { <img class="mr-3" width="64" src=image.clone()/> }
+                } else {
+                    html! {}
+                }
+            }
+            <div class="media-body">
+                <strong>{ htmlescape::decode_html(&menu.name).unwrap_or_else(|_| menu.name.clone()) }</strong>
+                { menu.price.map(|p| format!(" {}円", p)).unwrap_or_default() }
+                {
+                    if let Some(desc) = &menu.description {
+                        html! { <p class="small text-secondary mb-0">{ desc }</p> }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        </div>
+    }
+}
+
+/// 注意書きの分類と、その元の文。同じ分類は一つにまとめる
+fn caveat_flags(r: &RestaurantInfo) -> Vec<(CaveatFlag, String)> {
+    let mut ret: Vec<(CaveatFlag, String)> = vec![];
+    for c in &r.caveats {
+        for flag in &c.flags {
+            match ret.iter_mut().find(|(f, _)| f == flag) {
+                Some((_, text)) => {
+                    text.push('\n');
+                    text.push_str(&c.text);
+                }
+                None => ret.push((*flag, c.text.clone())),
+            }
+        }
+    }
+    ret
+}
+
+impl MainComponent {
+    fn nav_view(&self) -> Html {
+        let tab = |page: Page, label: &str| {
+            let class = if self.props.page == page {
+                "nav-link active"
+            } else {
+                "nav-link"
+            };
+            html! {
+                <li class="nav-item">
+                    <a class=class href="#" onclick=self.link.callback(move |ev: MouseEvent| {
+                        ev.prevent_default();
+                        Msg::ShowPage(page)
+                    })>{ label }</a>
+                </li>
+            }
+        };
+
+        html! {
+            <ul class="nav nav-tabs mb-4">
+                { tab(Page::Search, "営業中店舗検索") }
+                { tab(Page::Journal, "カレー日記") }
+            </ul>
+        }
+    }
+
+    fn save_journal(&mut self) {
+        let draft = std::mem::take(&mut self.props.journal_draft);
+        let shops = RESTAURANT_INFO.read().unwrap();
+
+        let i = match draft.shop {
+            Some(i) => i,
+            None => {
+                alert("店を選んでください");
+                self.props.journal_draft = draft;
+                return;
+            }
+        };
+
+        let entry = JournalEntry {
+            id: js_sys::Date::now() as u64,
+            code: shops[i].code,
+            url: shops[i].url.clone(),
+            time: draft.time.unwrap_or_else(get_jst_time),
+            rating: draft.rating,
+            notes: draft.notes,
+            has_photo: draft.photo.is_some(),
+        };
+
+        if let Some(photo) = draft.photo {
+            let key = entry.photo_key();
+            let link = self.link.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match photos::put(&key, &photo).await {
+                    Ok(()) => link.send_message(Msg::LoadPhotos),
+                    Err(e) => error!("failed to save photo: {:?}", e),
+                }
+            });
+        }
+
+        let mut p = storage::load();
+        p.journal.push(entry);
+        storage::save(&p);
+
+        // 日記を書いた店は訪問済みにする
+        if !self.props.visited[i] {
+            self.props.visited[i] = true;
+            set_visited(&self.props.visited);
+        }
+    }
+
+    /// カレー日記の入力欄と、これまでの訪問の一覧
+    fn journal_view(&self) -> Html {
+        let shops = RESTAURANT_INFO.read().unwrap();
+        let draft = &self.props.journal_draft;
+        let time = draft.time.unwrap_or_else(get_jst_time);
+
+        let mut journal = storage::load().journal;
+        journal.sort_by_key(|e| e.time);
+
+        html! {
+            <>
+            <h2>{"カレー日記に記録"}</h2>
+            <br/>
+
+            <form onsubmit=self.link.callback(|ev: FocusEvent| {
+                ev.prevent_default();
+                Msg::SaveJournal
+            })>
+                <div class="form-group row">
+                    <label for="journal-shop" class="col-sm-2 col-form-label">{"店"}</label>
+                    <div class="col-sm-6">
+                        <select id="journal-shop" class="form-control"
+                            onchange=self.link.callback(|ev| Msg::JournalShop(ev))>
+                            <option value="" selected=draft.shop.is_none()>{"選んでください"}</option>
+                            {
+                                for shops.iter().enumerate().map(|(i, r)| html! {
+                                    <option value=i.to_string() selected=draft.shop == Some(i)>
+                                        { format!("{} {}", r.code, htmlescape::decode_html(&r.name).unwrap_or_else(|_| r.name.clone())) }
+                                    </option>
+                                })
+                            }
+                        </select>
+                    </div>
+                </div>
+                <div class="form-group row">
+                    <label for="journal-time" class="col-sm-2 col-form-label">{"日時"}</label>
+                    <div class="col-sm-4">
+                        <input type="datetime-local" id="journal-time" class="form-control"
+                            value=time.format("%Y-%m-%dT%H:%M").to_string()
+                            onchange=self.link.callback(|ev| Msg::JournalTime(ev)) />
+                    </div>
+                </div>
+                <div class="form-group row">
+                    <label class="col-sm-2 col-form-label">{"評価"}</label>
+                    <div class="col-sm-6">
+                    {
+                        for (1..=5).map(|n| {
+                            let (class, star) = if n <= draft.rating {
+                                ("btn btn-link p-1 text-warning", "★")
+                            } else {
+                                ("btn btn-link p-1 text-secondary", "☆")
+                            };
+                            html! {
+                                <button type="button" class=class
+                                    onclick=self.link.callback(move |_| Msg::JournalRating(n))>
+                                    { star }
+                                </button>
+                            }
+                        })
+                    }
+                    </div>
+                </div>
+                <div class="form-group row">
+                    <label for="journal-notes" class="col-sm-2 col-form-label">{"メモ"}</label>
+                    <div class="col-sm-6">
+                        <textarea id="journal-notes" class="form-control" rows="3" value=draft.notes.clone()
+                            oninput=self.link.callback(|ev| Msg::JournalNotes(ev)) />
+                    </div>
+                </div>
+                <div class="form-group row">
+                    <label for="journal-photo" class="col-sm-2 col-form-label">{"写真"}</label>
+                    <div class="col-sm-6">
+                        <input type="file" id="journal-photo" class="form-control-file" accept="image/*"
+                            onchange=self.link.callback(|ev| Msg::JournalPhoto(ev)) />
+                    </div>
+                </div>
+                <button type="submit" class="btn btn-primary">{"記録する"}</button>
+            </form>
+
+            <hr/>
+
+            <h2>{ format!("これまでの訪問 ({})", journal.len()) }</h2>
+            <br/>
+
+            { for journal.iter().map(|e| self.journal_entry_view(e, &shops)) }
+            </>
+        }
+    }
+
+    fn journal_entry_view(&self, e: &JournalEntry, shops: &[RestaurantInfo]) -> Html {
+        let name = e.position(shops).map_or_else(
+            || format!("店舗コード {}", e.code),
+            |i| htmlescape::decode_html(&shops[i].name).unwrap_or_else(|_| shops[i].name.clone()),
+        );
+        let rating = (1..=5)
+            .map(|n| if n <= e.rating { '★' } else { '☆' })
+            .collect::<String>();
+        let id = e.id;
+
+        html! {
+            <div class="card mb-3">
+                {
+                    match self.photo_urls.get(&e.id) {
+                        Some(url) => html! { <img class="card-img-top" src=url.clone() /> },
+                        None => html! {},
+                    }
+                }
+                <div class="card-body">
+                    <h5 class="card-title">{ name }</h5>
+                    <h6 class="card-subtitle mb-2 text-muted">
+                        { e.time.format("%Y/%m/%d %H:%M").to_string() }
+                        <span class="text-warning ml-2">{ rating }</span>
+                    </h6>
+                    <p class="card-text" style="white-space: pre-wrap">{ &e.notes }</p>
+                    <button type="button" class="btn btn-link btn-sm text-danger p-0"
+                        onclick=self.link.callback(move |_| Msg::DeleteJournal(id))>
+                        {"削除"}
+                    </button>
+                </div>
+            </div>
+        }
+    }
+
+    /// 進捗の書き出しと読み込み
+    fn transfer_view(&self) -> Html {
+        let p = storage::load();
+
+        let qr = if self.props.show_transfer {
+            let location = web_sys::window().unwrap().location();
+            let url = format!(
+                "{}{}{}{}",
+                location.origin().unwrap(),
+                location.pathname().unwrap(),
+                storage::FRAGMENT_PREFIX,
+                storage::to_fragment(&p, &RESTAURANT_INFO.read().unwrap()),
+            );
+            match storage::qr_svg(&url) {
+                Ok(svg) => html! {
+                    <>
+                    <p><small>{"別の端末でこの QR コードを読み取るか、URL を開くと進捗を引き継げます"}</small></p>
+                    <img width="240" height="240"
+                        src=format!("data:image/svg+xml;base64,{}", base64::encode(svg)) />
+                    <input type="text" class="form-control form-control-sm mt-2" readonly=true value=url />
+                    </>
+                },
+                Err(e) => html! { <p>{ e }</p> },
+            }
+        } else {
+            html! {}
+        };
+
+        html! {
+            <>
+            <h2>{"進捗の引き継ぎ"}</h2>
+            <br/>
+
+            <p>
+                <a class="btn btn-outline-secondary btn-sm mr-2" download="kanda-curry-progress.json"
+                    href=format!("data:application/json;charset=utf-8,{}", urlencoding::encode(&storage::to_json(&p)))>
+                    {"ファイルに保存"}
+                </a>
+                <button type="button" class="btn btn-outline-secondary btn-sm"
+                    onclick=self.link.callback(|_| Msg::ShowTransfer)>
+                    { if self.props.show_transfer {"QR コードを隠す"} else {"QR コードを表示"} }
+                </button>
+            </p>
+            <div class="form-group">
+                <label for="import-file">{"ファイルから読み込む"}</label>
+                <input type="file" id="import-file" class="form-control-file" accept="application/json,.json"
+                    onchange=self.link.callback(|ev| Msg::ImportFile(ev)) />
+            </div>
+            { qr }
+            </>
+        }
+    }
+}
+
 fn get_jst_time() -> DateTime<FixedOffset> {
     let hour = 3600;
-    let tz = FixedOffset::east(9 * hour);
+    let tz = FixedOffset::east_opt(9 * hour).unwrap();
     Utc::now().with_timezone(&tz)
 }
 
-fn jp_weekday_name(wd: Weekday) -> &'static str {
-    match wd {
-        Weekday::Mon => "月",
-        Weekday::Tue => "火",
-        Weekday::Wed => "水",
-        Weekday::Thu => "木",
-        Weekday::Fri => "金",
-        Weekday::Sat => "土",
-        Weekday::Sun => "日",
+/// 営業中の営業時間の LO と閉店までの残り時間。`cutoff` が近ければ目立たせる
+fn remaining_view(bh: &BussinessHours, dt: &DateTime<FixedOffset>, cutoff: Cutoff) -> Html {
+    let rem: Remaining = match bh.remaining(dt) {
+        Some(rem) => rem,
+        None => return html! {},
+    };
+
+    let lo = match (&bh.lo, rem.to_lo) {
+        (Some(lo), Some(n)) if n > 0 => Some(format!("LO {}まで あと{}分", lo, n)),
+        (Some(_), _) => Some("ラストオーダー終了".to_string()),
+        (None, _) => None,
+    };
+    let close = format!("閉店 {}まで あと{}分", bh.close, rem.to_close);
+
+    let class = match rem.until(cutoff) {
+        0 => "list-group-item list-group-item-warning",
+        1..=30 => "list-group-item text-white bg-danger",
+        _ => "list-group-item",
+    };
+
+    html! {
+        <li class=class>
+            { lo.into_iter().chain(Some(close)).collect::<Vec<_>>().join(" / ") }
+        </li>
     }
 }
 
-const JAPANESE_HOLIDAY: &[(u32, u32)] = &[
-    // https://www8.cao.go.jp/chosei/shukujitsu/gaiyou.html
-    (1, 1),
-    (1, 13),
-    (2, 11),
-    (2, 23),
-    (2, 24),
-    (3, 20),
-    (4, 29),
-    (5, 3),
-    (5, 4),
-    (5, 5),
-    (5, 6),
-    (7, 23),
-    (7, 24),
-    (8, 10),
-    (9, 21),
-    (9, 22),
-    (11, 3),
-    (11, 23),
-];
-
-fn is_holiday(date: &Date<FixedOffset>) -> bool {
-    JAPANESE_HOLIDAY
-        .iter()
-        .any(|&(m, d)| m == date.month() && d == date.day())
+fn default_search_options() -> SearchOptions {
+    SearchOptions {
+        year_end: EVENT.read().unwrap().year_end,
+        ..Default::default()
+    }
 }
 
 fn get_visited() -> Vec<bool> {
-    let ls = web_sys::window().unwrap().local_storage().unwrap().unwrap();
-    let val = ls.get_item("visited").unwrap();
-
-    if let Some(val) = val {
-        val.chars().map(|c| c == '1').collect()
-    } else {
-        vec![false; RESTAURANT_INFO.read().unwrap().len()]
+    let shops = RESTAURANT_INFO.read().unwrap();
+    let mut ret = vec![false; shops.len()];
+    for s in storage::load().visited {
+        if let Some(i) = s.position(&shops) {
+            ret[i] = true;
+        }
     }
+    ret
 }
 
-fn set_visited(v: &Vec<bool>) {
-    let ls = web_sys::window().unwrap().local_storage().unwrap().unwrap();
-    let val = v
-        .iter()
-        .map(|b| if *b { '1' } else { '0' })
-        .collect::<String>();
-    ls.set_item("visited", &val).unwrap();
+fn set_visited(v: &[bool]) {
+    let shops = RESTAURANT_INFO.read().unwrap();
+    let mut p = storage::load();
+    let today = business_date(&get_jst_time());
+
+    // 訪問済みのままの店は最初に訪問済みにした日を残す
+    let prev = std::mem::take(&mut p.visited);
+    let (current, gone): (Vec<_>, Vec<_>) =
+        prev.into_iter().partition(|s| s.position(&shops).is_some());
+    p.visited = gone;
+    for (i, r) in shops.iter().enumerate().filter(|(i, _)| v[*i]) {
+        let date = current
+            .iter()
+            .find(|s| s.position(&shops) == Some(i))
+            .map_or(Some(today), |s| s.date);
+        p.visited.push(Visit::new(r, date));
+    }
+    storage::save(&p);
 }
 
-fn get_selected_course() -> Vec<bool> {
-    let ls = web_sys::window().unwrap().local_storage().unwrap().unwrap();
-    let val = ls.get_item("selected-course").unwrap();
-
-    if let Some(val) = val {
-        val.chars().map(|c| c == '1').collect()
-    } else {
-        vec![true; 5]
+/// 読み込んだ進捗で上書きしてよいか確かめて保存する。カレー日記は上書きせずに合わせる
+fn import_progress(mut p: Progress) -> bool {
+    let msg = format!(
+        "訪問済み {} 店の進捗を読み込みます。今の進捗は上書きされます（カレー日記は残します）。よろしいですか？",
+        p.visited.len()
+    );
+    if !web_sys::window()
+        .unwrap()
+        .confirm_with_message(&msg)
+        .unwrap_or(false)
+    {
+        return false;
     }
+    storage::merge_journal(&mut p.journal, storage::load().journal);
+    storage::save(&p);
+    true
 }
 
-fn set_selected_course(v: &Vec<bool>) {
-    let ls = web_sys::window().unwrap().local_storage().unwrap().unwrap();
-    let val = v
+fn alert(msg: &str) {
+    web_sys::window().unwrap().alert_with_message(msg).unwrap();
+}
+
+fn get_selected_course() -> Vec<bool> {
+    let p = storage::load();
+    EVENT
+        .read()
+        .unwrap()
+        .courses
         .iter()
-        .map(|b| if *b { '1' } else { '0' })
-        .collect::<String>();
-    ls.set_item("selected-course", &val).unwrap();
+        .map(|c| !p.unselected_courses.contains(&c.name))
+        .collect()
+}
+
+fn set_selected_course(v: &[bool]) {
+    let event = EVENT.read().unwrap();
+    let mut p = storage::load();
+    p.unselected_courses
+        .retain(|name| !event.courses.iter().any(|c| c.name == *name));
+    p.unselected_courses.extend(
+        event
+            .courses
+            .iter()
+            .zip(v)
+            .filter(|(_, b)| !**b)
+            .map(|(c, _)| c.name.clone()),
+    );
+    storage::save(&p);
 }
 
 #[wasm_bindgen(start)]
 pub async fn run_app() -> Result<(), JsValue> {
     web_logger::init();
 
-    let mut opts = RequestInit::new();
-    opts.method("GET");
-    opts.mode(RequestMode::Cors);
-    let url = "./info.json";
-    let req = Request::new_with_str_and_init(&url, &opts).unwrap();
-    let window = web_sys::window().unwrap();
+    // crawler と同じファイルを埋め込んで、二つの event.json がずれないようにする
+    let event: EventConfig =
+        serde_json::from_str(include_str!("../../crawler/event.json")).unwrap();
 
-    let resp = JsFuture::from(window.fetch_with_request(&req))
-        .await
-        .unwrap();
-    let resp: Response = resp.dyn_into().unwrap();
-    let json = JsFuture::from(resp.json().unwrap()).await.unwrap();
-    let vals: Vec<RestaurantInfo> = json.into_serde().unwrap();
+    let document = web_sys::window().unwrap().document().unwrap();
+    document.set_title(&format!("{} ツール", event.title));
+
+    *EVENT.write().unwrap() = event;
+
+    let text = fetch_text("./info.json").await.unwrap();
+    let vals: Vec<RestaurantInfo> = serde_json::from_str(&text).unwrap();
+
+    // 内閣府の祝日データから作ったもの。無ければ計算で求める
+    if let Some(text) = fetch_text("./holidays.json").await {
+        let hs: Vec<Holiday> = serde_json::from_str(&text).unwrap();
+        set_calendar(HolidayCalendar::from_holidays(hs));
+    }
 
     {
         let mut r = RESTAURANT_INFO.write().unwrap();
         *r = vals;
     }
 
+    // 引き継ぎ用の URL から開いたとき
+    let location = web_sys::window().unwrap().location();
+    let hash = location.hash().unwrap_or_default();
+    if let Some(fragment) = hash.strip_prefix(storage::FRAGMENT_PREFIX) {
+        match storage::from_fragment(fragment) {
+            Ok(p) => {
+                import_progress(p);
+            }
+            Err(e) => alert(&format!("進捗を読み込めませんでした: {}", e)),
+        }
+        location.set_hash("").unwrap();
+    }
+
     App::<Model>::new().mount_to_body();
 
     Ok(())
 }
+
+async fn fetch_text(url: &str) -> Option<String> {
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
+    let req = Request::new_with_str_and_init(url, &opts).unwrap();
+    let window = web_sys::window().unwrap();
+
+    let resp = JsFuture::from(window.fetch_with_request(&req))
+        .await
+        .unwrap();
+    let resp: Response = resp.dyn_into().unwrap();
+    if !resp.ok() {
+        return None;
+    }
+
+    let text = JsFuture::from(resp.text().unwrap()).await.unwrap();
+    text.as_string()
+}
@@ -0,0 +1,77 @@
+//! カレー日記の写真を IndexedDB に保存する
+//!
+//! localStorage には大きすぎるので、写真だけ日記の id をキーにして別に置く。
+
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+const DB_NAME: &str = "kanda-curry";
+const DB_VERSION: u32 = 1;
+const STORE: &str = "photos";
+
+/// `req` が終わるまで待って結果を返す
+async fn wait(req: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve: js_sys::Function, reject| {
+        let r = req.clone();
+        let onsuccess = Closure::once_into_js(move || {
+            let _ = resolve.call1(&JsValue::NULL, &r.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let r = req.clone();
+        let onerror = Closure::once_into_js(move || {
+            let err = r
+                .error()
+                .ok()
+                .flatten()
+                .map_or(JsValue::UNDEFINED, JsValue::from);
+            let _ = reject.call1(&JsValue::NULL, &err);
+        });
+        req.set_onsuccess(Some(onsuccess.unchecked_ref()));
+        req.set_onerror(Some(onerror.unchecked_ref()));
+    });
+    JsFuture::from(promise).await
+}
+
+async fn open() -> Result<IdbDatabase, JsValue> {
+    let factory = web_sys::window()
+        .unwrap()
+        .indexed_db()?
+        .ok_or_else(|| JsValue::from_str("IndexedDB is not available"))?;
+
+    let req = factory.open_with_u32(DB_NAME, DB_VERSION)?;
+    let onupgradeneeded = Closure::once_into_js(move |ev: web_sys::Event| {
+        let req: IdbOpenDbRequest = ev.target().unwrap().unchecked_into();
+        let db: IdbDatabase = req.result().unwrap().unchecked_into();
+        if !db.object_store_names().contains(STORE) {
+            db.create_object_store(STORE).unwrap();
+        }
+    });
+    req.set_onupgradeneeded(Some(onupgradeneeded.unchecked_ref()));
+
+    Ok(wait(&req).await?.unchecked_into())
+}
+
+pub async fn put(key: &str, photo: &Blob) -> Result<(), JsValue> {
+    let db = open().await?;
+    let tx = db.transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?;
+    wait(&tx.object_store(STORE)?.put_with_key(photo, &key.into())?).await?;
+    Ok(())
+}
+
+pub async fn get(key: &str) -> Result<Option<Blob>, JsValue> {
+    let db = open().await?;
+    let tx = db.transaction_with_str(STORE)?;
+    let v = wait(&tx.object_store(STORE)?.get(&key.into())?).await?;
+    Ok(if v.is_undefined() {
+        None
+    } else {
+        Some(v.unchecked_into())
+    })
+}
+
+pub async fn delete(key: &str) -> Result<(), JsValue> {
+    let db = open().await?;
+    let tx = db.transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?;
+    wait(&tx.object_store(STORE)?.delete(&key.into())?).await?;
+    Ok(())
+}
@@ -0,0 +1,485 @@
+//! localStorage に保存する進捗
+//!
+//! 以前は info.json の並び順に '0'/'1' を並べた文字列を "visited" と "selected-course" に
+//! 保存していたが、crawler が店の並びを変えるとずれてしまう。今は店舗コードとコース名で
+//! "progress" に保存し、古い形式は当時の並び順（legacy_order.json）と突き合わせて一度だけ移行する。
+//!
+//! 機種変更などで引き継ぐために、JSON ファイルと URL のフラグメントにも書き出せる。
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+use kanda_model::RestaurantInfo;
+use log::*;
+use serde::{Deserialize, Serialize};
+use web_sys::Storage;
+
+const PROGRESS_KEY: &str = "progress";
+
+/// 読めなかった "progress" を退避するキー。二つ目からは "-2" "-3" をつける
+const BACKUP_KEY: &str = "progress-backup";
+
+/// 保存形式を変えたら上げて、`load` に移行を書く
+const VERSION: u32 = 1;
+
+/// 訪問済みの店
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Visit {
+    pub code: usize,
+    /// 同じコードの店舗が複数あるときに区別する
+    pub url: String,
+    /// 訪問済みにした日（営業日）。移行したものにはない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+}
+
+impl Visit {
+    pub fn new(r: &RestaurantInfo, date: Option<NaiveDate>) -> Self {
+        Self {
+            code: r.code,
+            url: r.url.clone(),
+            date,
+        }
+    }
+
+    pub fn position(&self, shops: &[RestaurantInfo]) -> Option<usize> {
+        find_shop(shops, self.code, &self.url)
+    }
+}
+
+/// `shops` の中での位置。コードが重複している店だけ URL も比べる
+fn find_shop(shops: &[RestaurantInfo], code: usize, url: &str) -> Option<usize> {
+    let same_code = shops
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.code == code)
+        .collect::<Vec<_>>();
+
+    match same_code.as_slice() {
+        [(i, _)] => Some(*i),
+        _ => same_code
+            .iter()
+            .find(|(_, r)| r.url == url)
+            .map(|(i, _)| *i),
+    }
+}
+
+/// カレー日記の 1 回分の訪問
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    /// 書いた時刻（ミリ秒）。写真の IndexedDB のキーにも使う
+    pub id: u64,
+    pub code: usize,
+    pub url: String,
+    pub time: DateTime<FixedOffset>,
+    /// 1〜5
+    pub rating: u8,
+    pub notes: String,
+    /// 写真を IndexedDB に保存した
+    #[serde(default)]
+    pub has_photo: bool,
+}
+
+impl JournalEntry {
+    pub fn position(&self, shops: &[RestaurantInfo]) -> Option<usize> {
+        find_shop(shops, self.code, &self.url)
+    }
+
+    pub fn photo_key(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Progress {
+    pub version: u32,
+    /// info.json から消えた店も残しておく
+    pub visited: Vec<Visit>,
+    /// 選んでいないコース。新しく増えたコースは選んだ状態で始まる
+    pub unselected_courses: Vec<String>,
+    /// 古い順とは限らない
+    #[serde(default)]
+    pub journal: Vec<JournalEntry>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            visited: vec![],
+            unselected_courses: vec![],
+            journal: vec![],
+        }
+    }
+}
+
+/// 位置で保存していたころの info.json と event.json の並び
+#[derive(Deserialize)]
+struct LegacyOrder {
+    courses: Vec<String>,
+    shops: Vec<Visit>,
+}
+
+fn local_storage() -> Storage {
+    web_sys::window().unwrap().local_storage().unwrap().unwrap()
+}
+
+pub fn load() -> Progress {
+    let ls = local_storage();
+
+    if let Some(s) = ls.get_item(PROGRESS_KEY).unwrap() {
+        match serde_json::from_str::<Progress>(&s) {
+            Ok(p) if p.version == VERSION => return p,
+            Ok(p) => warn!("unknown progress version: {}", p.version),
+            Err(e) => warn!("invalid progress: {}", e),
+        }
+        // 次の `save` で空の進捗に上書きされるので、読めなかったものを残しておく
+        back_up(&ls, &s);
+        return Progress::default();
+    }
+
+    // 古い形式のキーは、移行に失敗したときのために消さずに残す
+    let p = migrate(
+        ls.get_item("visited").unwrap(),
+        ls.get_item("selected-course").unwrap(),
+    );
+    save(&p);
+    p
+}
+
+fn back_up(ls: &Storage, s: &str) {
+    let mut key = BACKUP_KEY.to_string();
+    let mut n = 1;
+    while let Some(b) = ls.get_item(&key).unwrap() {
+        // 保存するまでに何度も読むので、同じものは一つだけ残す
+        if b == s {
+            return;
+        }
+        n += 1;
+        key = format!("{}-{}", BACKUP_KEY, n);
+    }
+    ls.set_item(&key, s).unwrap();
+    warn!("kept the unreadable progress in {}", key);
+}
+
+pub fn save(p: &Progress) {
+    local_storage()
+        .set_item(PROGRESS_KEY, &serde_json::to_string(p).unwrap())
+        .unwrap();
+}
+
+fn migrate(visited: Option<String>, selected_course: Option<String>) -> Progress {
+    let legacy: LegacyOrder = serde_json::from_str(include_str!("legacy_order.json")).unwrap();
+
+    // 当時の店より長い文字列の余りは捨てる
+    let visited = visited
+        .unwrap_or_default()
+        .chars()
+        .zip(&legacy.shops)
+        .filter(|(c, _)| *c == '1')
+        .map(|(_, s)| s.clone())
+        .collect::<Vec<_>>();
+
+    let unselected_courses = selected_course
+        .unwrap_or_default()
+        .chars()
+        .zip(&legacy.courses)
+        .filter(|(c, _)| *c == '0')
+        .map(|(_, c)| c.clone())
+        .collect();
+
+    info!("migrated {} visited shops", visited.len());
+
+    Progress {
+        version: VERSION,
+        visited,
+        unselected_courses,
+        journal: vec![],
+    }
+}
+
+/// 引き継ぎ用の JSON ファイルの中身。日記の写真は含まない
+pub fn to_json(p: &Progress) -> String {
+    serde_json::to_string_pretty(p).unwrap()
+}
+
+pub fn from_json(s: &str) -> Result<Progress, String> {
+    let p: Progress = serde_json::from_str(s).map_err(|e| e.to_string())?;
+    if p.version != VERSION {
+        return Err(format!("unknown progress version: {}", p.version));
+    }
+    Ok(p)
+}
+
+/// 読み込んだ進捗の日記に、この端末の日記を合わせる
+///
+/// 同じ id の日記は読み込んだほうを使う。写真はこの端末の IndexedDB にしかないので、
+/// どちらかで写真を保存していれば残す。この端末の日記は消さないので、写真が宙に浮くこともない
+pub fn merge_journal(journal: &mut Vec<JournalEntry>, local: Vec<JournalEntry>) {
+    for e in local {
+        match journal.iter_mut().find(|j| j.id == e.id) {
+            Some(j) => j.has_photo |= e.has_photo,
+            None => journal.push(e),
+        }
+    }
+}
+
+/// URL のフラグメントの名前。`#progress=...`
+pub const FRAGMENT_PREFIX: &str = "#progress=";
+
+/// フラグメントの形式。QR コードに収まるように JSON ではなくバイト列にする
+const FRAGMENT_VERSION: u8 = 1;
+
+/// 訪問日を 2000-01-01 からの日数で書く
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
+}
+
+/// 店舗コードの最上位ビットが立っていたら URL が続く
+const HAS_URL: u16 = 0x8000;
+
+/// URL のフラグメントに載せる文字列（base64url）
+///
+/// バイト列は、版、選んでいないコース（個数、長さと UTF-8）、訪問済みの店の並び。
+/// 店は店舗コード（u16）と訪問日（u16、2000-01-01 を 1 とする日数、不明なら 0）で、
+/// `shops` の中でコードが重複している店だけ URL（長さと UTF-8）を付ける。
+pub fn to_fragment(p: &Progress, shops: &[RestaurantInfo]) -> String {
+    let mut buf = vec![FRAGMENT_VERSION];
+
+    // 255 バイトまで。文字の途中では切らない
+    let push_str = |buf: &mut Vec<u8>, s: &str| {
+        let mut len = s.len().min(255);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        let bytes = &s.as_bytes()[..len];
+        buf.push(bytes.len() as u8);
+        buf.extend_from_slice(bytes);
+    };
+
+    buf.push(p.unselected_courses.len() as u8);
+    for c in &p.unselected_courses {
+        push_str(&mut buf, c);
+    }
+
+    for v in &p.visited {
+        let dup = shops.iter().filter(|r| r.code == v.code).count() > 1;
+        let code = v.code as u16 & !HAS_URL | if dup { HAS_URL } else { 0 };
+        let days = v
+            .date
+            .map_or(0, |d| (d - epoch()).num_days().clamp(0, 0xfffe) as u16 + 1);
+
+        buf.extend_from_slice(&code.to_be_bytes());
+        buf.extend_from_slice(&days.to_be_bytes());
+        if dup {
+            push_str(&mut buf, &v.url);
+        }
+    }
+
+    base64::encode_config(&buf, base64::URL_SAFE_NO_PAD)
+}
+
+pub fn from_fragment(s: &str) -> Result<Progress, String> {
+    let buf = base64::decode_config(s, base64::URL_SAFE_NO_PAD).map_err(|e| e.to_string())?;
+    let mut rest = buf.as_slice();
+
+    fn take<'a>(rest: &mut &'a [u8], n: usize) -> Result<&'a [u8], String> {
+        if rest.len() < n {
+            return Err("unexpected end of data".to_string());
+        }
+        let (ret, r) = rest.split_at(n);
+        *rest = r;
+        Ok(ret)
+    }
+    fn take_str(rest: &mut &[u8]) -> Result<String, String> {
+        let len = take(rest, 1)?[0] as usize;
+        String::from_utf8(take(rest, len)?.to_vec()).map_err(|e| e.to_string())
+    }
+    fn take_u16(rest: &mut &[u8]) -> Result<u16, String> {
+        let b = take(rest, 2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    let version = take(&mut rest, 1)?[0];
+    if version != FRAGMENT_VERSION {
+        return Err(format!("unknown fragment version: {}", version));
+    }
+
+    let n = take(&mut rest, 1)?[0];
+    let unselected_courses = (0..n)
+        .map(|_| take_str(&mut rest))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut visited = vec![];
+    while !rest.is_empty() {
+        let code = take_u16(&mut rest)?;
+        let days = take_u16(&mut rest)?;
+        let url = if code & HAS_URL != 0 {
+            take_str(&mut rest)?
+        } else {
+            String::new()
+        };
+        visited.push(Visit {
+            code: (code & !HAS_URL) as usize,
+            url,
+            date: if days == 0 {
+                None
+            } else {
+                Some(epoch() + Duration::days(days as i64 - 1))
+            },
+        });
+    }
+
+    // 日記は URL に載せない
+    Ok(Progress {
+        version: VERSION,
+        visited,
+        unselected_courses,
+        journal: vec![],
+    })
+}
+
+/// `url` の QR コードの SVG
+pub fn qr_svg(url: &str) -> Result<String, String> {
+    let code = qrcode::QrCode::with_error_correction_level(url, qrcode::EcLevel::L)
+        .map_err(|e| e.to_string())?;
+    Ok(code
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(240, 240)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn shop(code: usize, url: &str) -> RestaurantInfo {
+        serde_json::from_value(json!({
+            "code": code,
+            "name": "",
+            "course": "A",
+            "url": url,
+            "tn_url": "",
+            "address": "",
+            "business_hours": [],
+            "business_hours_label": null,
+            "alt_business_hours": null,
+            "business_hours_raw": "",
+            "regular_holiday": [],
+            "irregular_holiday": false,
+            "regular_holiday_raw": "",
+        }))
+        .unwrap()
+    }
+
+    fn visit(code: usize, url: &str, date: Option<(i32, u32, u32)>) -> Visit {
+        Visit {
+            code,
+            url: url.to_string(),
+            date: date.map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap()),
+        }
+    }
+
+    fn summary(p: &Progress) -> serde_json::Value {
+        json!([p.version, p.unselected_courses, p.visited])
+    }
+
+    fn entry(id: u64, notes: &str, has_photo: bool) -> JournalEntry {
+        JournalEntry {
+            id,
+            code: 1102,
+            url: "https://kanda-curry.com/?p=6".to_string(),
+            time: DateTime::parse_from_rfc3339("2020-09-01T12:00:00+09:00").unwrap(),
+            rating: 5,
+            notes: notes.to_string(),
+            has_photo,
+        }
+    }
+
+    #[test]
+    fn merge_journal_keeps_local() {
+        let mut journal = vec![entry(2, "読み込んだ", false), entry(3, "別の端末", true)];
+        let local = vec![entry(1, "この端末", true), entry(2, "この端末", true)];
+        merge_journal(&mut journal, local);
+
+        let mut merged = journal
+            .iter()
+            .map(|e| (e.id, e.notes.as_str(), e.has_photo))
+            .collect::<Vec<_>>();
+        merged.sort();
+        assert_eq!(
+            merged,
+            vec![
+                (1, "この端末", true),
+                (2, "読み込んだ", true),
+                (3, "別の端末", true),
+            ]
+        );
+
+        // URL からの読み込みには日記がない
+        let mut journal = vec![];
+        merge_journal(&mut journal, vec![entry(1, "この端末", true)]);
+        assert_eq!(journal.len(), 1);
+    }
+
+    #[test]
+    fn fragment_round_trip() {
+        let shops = vec![
+            shop(1102, "https://kanda-curry.com/?p=6"),
+            shop(1815, "https://kanda-curry.com/?p=4882"),
+            shop(1815, "https://kanda-curry.com/?p=8296"),
+        ];
+        let p = Progress {
+            visited: vec![
+                visit(1102, "https://kanda-curry.com/?p=6", Some((2020, 9, 1))),
+                visit(1815, "https://kanda-curry.com/?p=8296", Some((2000, 1, 1))),
+                visit(1815, "https://kanda-curry.com/?p=4882", None),
+            ],
+            unselected_courses: vec!["B".to_string(), "Eコース".to_string()],
+            ..Progress::default()
+        };
+
+        let decoded = from_fragment(&to_fragment(&p, &shops)).unwrap();
+
+        // コードが重複していない店の URL は載せない
+        let expected = Progress {
+            visited: vec![
+                visit(1102, "", Some((2020, 9, 1))),
+                visit(1815, "https://kanda-curry.com/?p=8296", Some((2000, 1, 1))),
+                visit(1815, "https://kanda-curry.com/?p=4882", None),
+            ],
+            ..p
+        };
+        assert_eq!(summary(&decoded), summary(&expected));
+
+        for v in &decoded.visited {
+            assert!(v.position(&shops).is_some());
+        }
+    }
+
+    #[test]
+    fn fragment_long_course_name() {
+        // 255 バイト目が「あ」の途中になる
+        let name = format!("x{}", "あ".repeat(100));
+        let p = Progress {
+            unselected_courses: vec![name],
+            ..Progress::default()
+        };
+
+        let decoded = from_fragment(&to_fragment(&p, &[])).unwrap();
+        assert_eq!(
+            decoded.unselected_courses,
+            vec![format!("x{}", "あ".repeat(84))]
+        );
+    }
+
+    #[test]
+    fn fragment_errors() {
+        assert!(from_fragment("not base64!").is_err());
+        // 版が違う
+        assert!(from_fragment(&base64::encode_config([9u8, 0], base64::URL_SAFE_NO_PAD)).is_err());
+        // 訪問済みの店が途中で切れている
+        assert!(
+            from_fragment(&base64::encode_config([1u8, 0, 4], base64::URL_SAFE_NO_PAD)).is_err()
+        );
+    }
+}